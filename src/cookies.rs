@@ -1,4 +1,29 @@
-use spin_sdk::http::{Response, ResponseBuilder};
+use std::fmt::Display;
+use std::time::SystemTime;
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use spin_sdk::http::{Request, Response, ResponseBuilder};
+
+use crate::error::ContribError;
+use crate::httpdate::format_http_date;
+use crate::request::Contrib;
+
+/// Prefix for cookies bound to the host which set them
+const HOST_PREFIX: &str = "__Host-";
+/// Prefix for cookies which must be set from a secure origin
+const SECURE_PREFIX: &str = "__Secure-";
+
+/// The maximum size (in bytes) of a serialized cookie browsers are required to store (RFC 6265, section 6.1)
+pub const MAX_COOKIE_SIZE: usize = 4096;
+
+/// Characters which are not allowed in cookie values (RFC 6265) and are therefore percent-encoded
+const COOKIE_VALUE_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b',')
+    .add(b';')
+    .add(b'\\')
+    .add(b'%');
 
 /// Use this enum to control SameSite property when creating cookies
 pub enum SameSite {
@@ -13,15 +38,25 @@ pub enum SameSite {
 /// Representation of a cookie
 pub struct Cookie {
     /// The name of the cookie
-    name: &'static str,
+    name: String,
     /// The value of the cookie
-    value: &'static str,
+    value: String,
     /// Whether or not the cookie should be sent over HTTPS only
     secure: bool,
     /// Whether or not the cookie should be accessible via JavaScript
     http_only: bool,
     /// The SameSite property of the cookie
     same_site: SameSite,
+    /// The number of seconds until the cookie expires
+    max_age: Option<u64>,
+    /// The path the cookie is scoped to
+    path: Option<String>,
+    /// The point in time the cookie expires
+    expires: Option<SystemTime>,
+    /// The domain the cookie is scoped to
+    domain: Option<String>,
+    /// Whether or not the cookie is stored using partitioned storage (CHIPS)
+    partitioned: bool,
 }
 
 /// Trait for conversion into SameSite
@@ -56,42 +91,271 @@ impl Cookie {
     /// * `http_only` - Whether or not the cookie should be accessible via JavaScript
     /// * `same_site` - The SameSite property of the cookie
     pub fn new(
-        name: &'static str,
-        value: &'static str,
+        name: impl Into<String>,
+        value: impl Into<String>,
         secure: bool,
         http_only: bool,
         same_site: impl IntoSameSite,
     ) -> Self {
         Cookie {
-            name,
-            value,
+            name: name.into(),
+            value: value.into(),
             secure,
             http_only,
             same_site: same_site.into_same_site(),
+            max_age: None,
+            path: None,
+            expires: None,
+            domain: None,
+            partitioned: false,
+        }
+    }
+
+    /// Creates a cookie with hardened defaults
+    ///
+    /// The cookie is `Secure` (only sent over HTTPS), `HttpOnly` (not accessible via JavaScript)
+    /// and uses `SameSite=Lax` (sent on top-level navigations, but not on cross-site subrequests).
+    /// Unlike [`Cookie::secure_session`], neither a name prefix nor a path is applied. Use
+    /// [`Cookie::new`] if the cookie must be readable by JavaScript or sent over plain HTTP
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the cookie
+    /// * `value` - The value of the cookie
+    pub fn secure_defaults(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Cookie::new(name, value, true, true, SameSite::Lax)
+    }
+
+    /// Creates a session cookie following best practices
+    ///
+    /// The cookie is `Secure`, `HttpOnly`, uses `SameSite=Lax` and is scoped to `Path=/`.
+    /// Its name is prefixed with `__Host-` (unless already present), which tells browsers to only accept
+    /// the cookie if it was set from a secure origin, has `Path=/` and no `Domain` attribute
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the cookie (without the `__Host-` prefix)
+    /// * `value` - The value of the cookie
+    pub fn secure_session(name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into();
+        let name = if name.starts_with(HOST_PREFIX) {
+            name
+        } else {
+            format!("{}{}", HOST_PREFIX, name)
+        };
+        Cookie::secure_defaults(name, value).with_path("/")
+    }
+
+    /// Creates a cookie carrying an authentication token (e.g. an access or refresh token)
+    ///
+    /// The cookie is `Secure`, `HttpOnly`, uses `SameSite=Strict` and is scoped to `Path=/`.
+    /// By design, it can't be made readable by JavaScript, because no setter changes `HttpOnly`
+    /// (use [`Cookie::new`] if scripts must read the value). This protects the token from being stolen via XSS
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the cookie
+    /// * `value` - The token
+    pub fn auth_token(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Cookie::new(name, value, true, true, SameSite::Strict).with_path("/")
+    }
+
+    /// Creates a cookie which instructs browsers to remove the cookie with the provided name
+    ///
+    /// The cookie has an empty value, `Max-Age=0` and `Path=/`. Cookies are only removed if the path matches,
+    /// use [`Cookie::with_path`] for cookies scoped to other paths. The cookie is `Secure` if the name
+    /// uses the `__Host-` or `__Secure-` prefix, because browsers reject such cookies otherwise
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the cookie to remove
+    pub fn removal(name: impl Into<String>) -> Self {
+        let name = name.into();
+        let secure = name.starts_with(HOST_PREFIX) || name.starts_with(SECURE_PREFIX);
+        Cookie::new(name, "", secure, true, SameSite::Lax)
+            .with_max_age(0)
+            .with_path("/")
+    }
+
+    /// Creates a replacement for a cookie sent with the request, carrying a new value (e.g. to rotate a session id)
+    ///
+    /// Requests only carry cookie names and values, so attributes can't be read from the request. The name is
+    /// carried over and the cookie is `Secure`, `HttpOnly`, uses `SameSite=Lax` and is scoped to `Path=/`,
+    /// which matches [`Cookie::secure_session`] and is valid for `__Host-` and `__Secure-` prefixed names.
+    /// If the request doesn't carry a cookie with the provided name, `None` is returned
+    ///
+    /// # Arguments
+    ///
+    /// * `req` - The incoming request
+    /// * `name` - The name of the cookie to rotate
+    /// * `new_value` - The new value of the cookie
+    pub fn rotate_from(req: &Request, name: &str, new_value: impl Into<String>) -> Option<Self> {
+        req.get_cookie(name)?;
+        Some(Cookie::secure_defaults(name, new_value).with_path("/"))
+    }
+
+    /// Parses a `Set-Cookie` header value into a Cookie
+    ///
+    /// `Secure`, `HttpOnly`, `SameSite`, `Max-Age`, `Path`, `Domain` and `Partitioned` are recognized
+    /// (case-insensitively), other attributes are ignored. Browsers treat cookies without `SameSite` as `Lax`,
+    /// which is why a missing `SameSite` attribute results in `SameSite::Lax`.
+    /// The value is kept verbatim (not decoded). If the name is missing, `None` is returned
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value of the `Set-Cookie` header
+    pub fn parse_set_cookie(value: &str) -> Option<Self> {
+        let mut parts = value.split(';');
+        let (name, cookie_value) = parts.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
         }
+        let mut cookie = Cookie::new(name, cookie_value.trim(), false, false, SameSite::Lax);
+        for attribute in parts {
+            let (key, attribute_value) = match attribute.split_once('=') {
+                Some((key, attribute_value)) => (key.trim(), attribute_value.trim()),
+                None => (attribute.trim(), ""),
+            };
+            match key.to_lowercase().as_str() {
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => cookie.same_site = attribute_value.into_same_site(),
+                "max-age" => cookie.max_age = attribute_value.parse().ok(),
+                "path" => cookie.path = Some(attribute_value.to_string()),
+                "domain" => cookie.domain = Some(attribute_value.to_string()),
+                "partitioned" => cookie.partitioned = true,
+                _ => {}
+            }
+        }
+        Some(cookie)
     }
+
+    /// Sets the Max-Age property (in seconds) of the cookie
+    pub fn with_max_age(mut self, max_age: u64) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Sets the Path property of the cookie
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Sets the Expires property of the cookie
+    pub fn with_expires(mut self, expires: SystemTime) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    /// Sets the Domain property of the cookie
+    pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Sets the Partitioned property of the cookie (Cookies Having Independent Partitioned State)
+    pub fn with_partitioned(mut self, partitioned: bool) -> Self {
+        self.partitioned = partitioned;
+        self
+    }
+
+    /// Validates that browsers will store the cookie.
+    ///
+    /// Partitioned cookies (CHIPS) must use `SameSite=None` (which also sets `Secure`), and the serialized
+    /// cookie must not exceed `MAX_COOKIE_SIZE` (see [`Cookie::validate_size`])
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_contrib_http::cookies::{Cookie, SameSite};
+    ///
+    /// let cookie = Cookie::new("embed", "abc", true, true, SameSite::None).with_partitioned(true);
+    /// assert!(cookie.validate().is_ok());
+    ///
+    /// let cookie = Cookie::new("embed", "abc", true, true, SameSite::Lax).with_partitioned(true);
+    /// assert!(cookie.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), ContribError> {
+        if self.partitioned && !matches!(self.same_site, SameSite::None) {
+            return Err(ContribError::InvalidHeader(format!(
+                "partitioned cookie {} requires SameSite=None",
+                self.name
+            )));
+        }
+        self.validate_size()
+    }
+
+    /// Validates that the serialized cookie (`name=value` plus attributes) doesn't exceed `MAX_COOKIE_SIZE`
+    ///
+    /// Browsers silently drop cookies exceeding the limit, so validating before setting surfaces the problem early
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_contrib_http::cookies::Cookie;
+    ///
+    /// assert!(Cookie::secure_defaults("session", "abc").validate_size().is_ok());
+    /// assert!(Cookie::secure_defaults("session", "a".repeat(4096)).validate_size().is_err());
+    /// ```
+    pub fn validate_size(&self) -> Result<(), ContribError> {
+        let size = self.to_string().len();
+        if size > MAX_COOKIE_SIZE {
+            return Err(ContribError::InvalidHeader(format!(
+                "cookie {} is {} bytes long, exceeding the limit of {} bytes",
+                self.name, size, MAX_COOKIE_SIZE
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Percent-encodes all characters not allowed in cookie values
+pub(crate) fn encode_cookie_value(value: &str) -> String {
+    utf8_percent_encode(value, COOKIE_VALUE_ENCODE_SET).to_string()
 }
 
-impl ToString for Cookie {
-    fn to_string(&self) -> String {
-        let mut value = self.value.to_string();
-        let mut secure = self.secure;
+/// Decodes a percent-encoded cookie value. If the decoded value isn't valid UTF-8, the raw value is returned
+pub(crate) fn decode_cookie_value(value: &str) -> String {
+    percent_decode_str(value)
+        .decode_utf8()
+        .map(|decoded| decoded.to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
 
+/// Serializes the cookie as `Set-Cookie` header value
+///
+/// Attributes are emitted in a canonical order:
+/// `name=value; Expires; Max-Age; Domain; Path; Secure; HttpOnly; SameSite; Partitioned`
+impl Display for Cookie {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", format_http_date(expires))?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age)?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if self.secure || matches!(self.same_site, SameSite::None) {
+            write!(f, "; Secure")?;
+        }
         if self.http_only {
-            value.push_str("; HttpOnly");
+            write!(f, "; HttpOnly")?;
         }
         match self.same_site {
-            SameSite::Strict => value.push_str("; SameSite=Strict"),
-            SameSite::Lax => value.push_str("; SameSite=Lax"),
-            SameSite::None => {
-                secure = true;
-                value.push_str("; SameSite=None");
-            }
+            SameSite::Strict => write!(f, "; SameSite=Strict")?,
+            SameSite::Lax => write!(f, "; SameSite=Lax")?,
+            SameSite::None => write!(f, "; SameSite=None")?,
         }
-        if secure {
-            value.push_str("; Secure");
+        if self.partitioned {
+            write!(f, "; Partitioned")?;
         }
-        format!("{}={}", self.name, value)
+        Ok(())
     }
 }
 
@@ -99,12 +363,31 @@ impl ToString for Cookie {
 pub trait CookieResponseBuilder {
     /// Build an HTTP response with a single cookie
     fn build_with_cookie(&mut self, cookie: Cookie) -> Response;
+
+    /// Sets the cookie (`Set-Cookie` header) and returns the builder for further chaining
+    ///
+    /// Responses carry a single `Set-Cookie` header, so adding another cookie replaces the previous one
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::ResponseBuilder;
+    /// use spin_contrib_http::cookies::{Cookie, CookieResponseBuilder};
+    ///
+    /// let response = ResponseBuilder::new(200)
+    ///     .add_cookie(Cookie::secure_defaults("session", "abc"))
+    ///     .header("x-custom", "value")
+    ///     .build();
+    /// ```
+    fn add_cookie(&mut self, cookie: Cookie) -> &mut Self;
 }
 
 impl CookieResponseBuilder for ResponseBuilder {
     fn build_with_cookie(&mut self, cookie: Cookie) -> Response {
-        self.header(http::header::SET_COOKIE.as_str(), cookie.to_string());
-        self.build()
+        self.add_cookie(cookie).build()
+    }
+
+    fn add_cookie(&mut self, cookie: Cookie) -> &mut Self {
+        self.header(http::header::SET_COOKIE.as_str(), cookie.to_string())
     }
 }
 
@@ -119,10 +402,29 @@ mod tests {
         let cookie = Cookie::new(name, value, false, false, SameSite::Strict);
         let sut = ResponseBuilder::new(200).build_with_cookie(cookie);
 
-        assert_eq!(
-            sut.header(http::header::SET_COOKIE.as_str()).is_some(),
-            true
-        );
+        assert!(sut.header(http::header::SET_COOKIE.as_str()).is_some());
+    }
+
+    #[test]
+    fn add_cookie_should_allow_chaining_further_headers() {
+        let cookie = Cookie::new("a", "b", false, false, SameSite::Strict);
+        let sut = ResponseBuilder::new(200)
+            .add_cookie(cookie)
+            .header("x-custom", "value")
+            .build();
+
+        let cookie = sut
+            .header(http::header::SET_COOKIE.as_str())
+            .expect("Header SET_COOKIE not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(cookie, "a=b; SameSite=Strict");
+        let custom = sut
+            .header("x-custom")
+            .expect("Header x-custom not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(custom, "value");
     }
 
     #[test]
@@ -147,7 +449,7 @@ mod tests {
     fn add_cookie_should_add_secure_flag_when_same_site_is_none() {
         let name = "a";
         let value = "b";
-        let expected = format!("{}={}; SameSite=None; Secure", name, value);
+        let expected = format!("{}={}; Secure; SameSite=None", name, value);
 
         let cookie = Cookie::new(name, value, false, false, SameSite::None);
         let sut = ResponseBuilder::new(200).build_with_cookie(cookie);
@@ -159,4 +461,180 @@ mod tests {
             .unwrap();
         assert_eq!(actual, &expected);
     }
+
+    #[test]
+    fn cookie_should_serialize_max_age_and_path() {
+        let cookie = Cookie::new("a", "b", false, true, SameSite::Lax)
+            .with_max_age(60)
+            .with_path("/");
+
+        assert_eq!(
+            cookie.to_string(),
+            "a=b; Max-Age=60; Path=/; HttpOnly; SameSite=Lax"
+        );
+    }
+
+    #[test]
+    fn cookie_should_serialize_attributes_in_canonical_order() {
+        let cookie = Cookie::new("id", "a3fWa", true, true, SameSite::None)
+            .with_partitioned(true)
+            .with_path("/docs")
+            .with_domain("example.com")
+            .with_max_age(2_592_000)
+            .with_expires(std::time::UNIX_EPOCH + std::time::Duration::from_secs(784_111_777));
+
+        assert_eq!(
+            cookie.to_string(),
+            "id=a3fWa; Expires=Sun, 06 Nov 1994 08:49:37 GMT; Max-Age=2592000; Domain=example.com; Path=/docs; Secure; HttpOnly; SameSite=None; Partitioned"
+        );
+    }
+
+    #[test]
+    fn parse_set_cookie_should_read_attributes() {
+        let sut = Cookie::parse_set_cookie("id=abc; HttpOnly; SameSite=Lax; Secure; Foo=bar")
+            .expect("Could not parse cookie");
+
+        assert_eq!(sut.name, "id");
+        assert_eq!(sut.value, "abc");
+        assert!(sut.http_only);
+        assert!(sut.secure);
+        assert!(matches!(sut.same_site, SameSite::Lax));
+        assert_eq!(sut.max_age, None);
+        assert_eq!(sut.path, None);
+
+        let sut = Cookie::parse_set_cookie(
+            "id=abc; Max-Age=60; Domain=example.com; Path=/docs; SameSite=strict",
+        )
+        .expect("Could not parse cookie");
+        assert_eq!(sut.max_age, Some(60));
+        assert_eq!(sut.domain.as_deref(), Some("example.com"));
+        assert_eq!(sut.path.as_deref(), Some("/docs"));
+        assert!(matches!(sut.same_site, SameSite::Strict));
+        assert!(!sut.secure);
+
+        assert!(Cookie::parse_set_cookie("no-equals-sign").is_none());
+        assert!(Cookie::parse_set_cookie("=abc").is_none());
+    }
+
+    #[test]
+    fn parse_set_cookie_should_round_trip_with_to_string() {
+        let cookie = Cookie::new("id", "a3fWa", true, true, SameSite::None)
+            .with_partitioned(true)
+            .with_path("/docs")
+            .with_domain("example.com")
+            .with_max_age(60)
+            .to_string();
+
+        let sut = Cookie::parse_set_cookie(&cookie).expect("Could not parse cookie");
+        assert_eq!(sut.to_string(), cookie);
+    }
+
+    #[test]
+    fn cookie_values_should_round_trip_through_percent_encoding() {
+        let value = "Saved; see \"you\", 100% ✓";
+        let encoded = encode_cookie_value(value);
+
+        assert_eq!(
+            encoded,
+            "Saved%3B%20see%20%22you%22%2C%20100%25%20%E2%9C%93"
+        );
+        assert_eq!(decode_cookie_value(&encoded), value);
+    }
+
+    #[test]
+    fn secure_defaults_should_set_hardened_attributes() {
+        let cookie = Cookie::secure_defaults("theme", "dark");
+        assert_eq!(
+            cookie.to_string(),
+            "theme=dark; Secure; HttpOnly; SameSite=Lax"
+        );
+    }
+
+    #[test]
+    fn removal_should_expire_cookie_immediately() {
+        let test_data = vec![
+            (
+                "session",
+                "session=; Max-Age=0; Path=/; HttpOnly; SameSite=Lax",
+            ),
+            (
+                "__Host-session",
+                "__Host-session=; Max-Age=0; Path=/; Secure; HttpOnly; SameSite=Lax",
+            ),
+        ];
+        for (name, expected) in test_data {
+            assert_eq!(Cookie::removal(name).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn auth_token_should_set_strict_attributes() {
+        let sut = Cookie::auth_token("access_token", "abc").with_max_age(900);
+        assert_eq!(
+            sut.to_string(),
+            "access_token=abc; Max-Age=900; Path=/; Secure; HttpOnly; SameSite=Strict"
+        );
+    }
+
+    #[test]
+    fn secure_session_should_set_prefix_and_secure_attributes() {
+        let cookie = Cookie::secure_session("session", "abc");
+        assert_eq!(
+            cookie.to_string(),
+            "__Host-session=abc; Path=/; Secure; HttpOnly; SameSite=Lax"
+        );
+
+        let cookie = Cookie::secure_session("__Host-session", "abc");
+        assert!(cookie.to_string().starts_with("__Host-session=abc;"));
+    }
+
+    #[test]
+    fn rotate_from_should_replace_value_of_request_cookie() {
+        let req =
+            spin_sdk::http::RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo.bar")
+                .header(
+                    http::header::COOKIE.as_str(),
+                    "theme=dark; __Host-session=old",
+                )
+                .body(())
+                .build();
+
+        let sut = Cookie::rotate_from(&req, "__Host-session", "new").expect("Cookie not present");
+        assert_eq!(
+            sut.to_string(),
+            "__Host-session=new; Path=/; Secure; HttpOnly; SameSite=Lax"
+        );
+        assert!(Cookie::rotate_from(&req, "session", "new").is_none());
+    }
+
+    #[test]
+    fn validate_should_require_same_site_none_for_partitioned_cookies() {
+        let test_data = vec![
+            (SameSite::None, true, true),
+            (SameSite::Lax, true, false),
+            (SameSite::Strict, true, false),
+            (SameSite::Lax, false, true),
+        ];
+        for (same_site, partitioned, expected) in test_data {
+            let sut = Cookie::new("a", "b", true, true, same_site).with_partitioned(partitioned);
+            assert_eq!(sut.validate().is_ok(), expected, "{}", sut);
+        }
+
+        let sut = Cookie::new("a", "b".repeat(MAX_COOKIE_SIZE), true, true, SameSite::Lax);
+        assert!(sut.validate().is_err());
+    }
+
+    #[test]
+    fn validate_size_should_reject_cookies_exceeding_the_limit() {
+        // "a=" plus "; SameSite=Strict" add 19 bytes to the value
+        let test_data = vec![
+            (MAX_COOKIE_SIZE - 20, true),
+            (MAX_COOKIE_SIZE - 19, true),
+            (MAX_COOKIE_SIZE - 18, false),
+        ];
+        for (value_len, expected) in test_data {
+            let sut = Cookie::new("a", "b".repeat(value_len), false, false, SameSite::Strict);
+            assert_eq!(sut.validate_size().is_ok(), expected, "{}", value_len);
+        }
+    }
 }
@@ -1,6 +1,52 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
 
-use super::NO_ORIGINS;
+use crate::error::ContribError;
+
+use super::{
+    is_origin_allowed, is_well_formed_origin, normalize_origin, ALL_HEADERS, ALL_METHODS,
+    ALL_ORIGINS, NO_ORIGINS, NULL_ORIGIN,
+};
+
+/// HTTP methods accepted by [`CorsConfig::try_new`]
+const KNOWN_METHODS: [&str; 9] = [
+    "GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH",
+];
+
+/// Controls how long browsers may cache preflight responses (`Access-Control-Max-Age`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaxAge {
+    /// Cache preflight responses for the provided number of seconds
+    Cache(u32),
+    /// Disable caching of preflight responses (`Access-Control-Max-Age: -1`)
+    NoCache,
+}
+
+impl Display for MaxAge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MaxAge::Cache(secs) => write!(f, "{}", secs),
+            MaxAge::NoCache => write!(f, "-1"),
+        }
+    }
+}
+
+/// A custom predicate deciding whether or not an origin is allowed
+pub type OriginPredicate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// The recommended max age (in seconds) of preflight responses, see [`CorsConfig::with_recommended_max_age`]
+pub const RECOMMENDED_MAX_AGE: u32 = 600;
+
+/// The CORS-safelisted response headers, which are always exposed to scripts
+const SAFELISTED_RESPONSE_HEADERS: [&str; 7] = [
+    "cache-control",
+    "content-language",
+    "content-length",
+    "content-type",
+    "expires",
+    "last-modified",
+    "pragma",
+];
 
 /// This struct is used to configure CORS support
 pub struct CorsConfig {
@@ -8,12 +54,30 @@ pub struct CorsConfig {
     pub(crate) allowed_origins: String,
     /// The HTTP methods to allow in CORS (separated by commas)
     pub(crate) allowed_methods: String,
-    /// The HTTP headers to allow in CORS (separated by commas)
+    /// The HTTP headers to allow in CORS (lowercased and separated by commas)
     pub(crate) allowed_headers: String,
     /// Whether or not to allow credentials in CORS
     pub(crate) allow_credentials: bool,
     /// The max age to allow in CORS
-    pub(crate) max_age: Option<u32>,
+    pub(crate) max_age: Option<MaxAge>,
+    /// Whether or not preflight responses echo only the requested method instead of all allowed methods
+    pub(crate) reflect_requested_method: bool,
+    /// The status code used to reject preflights requesting headers which are not allowed
+    pub(crate) disallowed_headers_status: http::StatusCode,
+    /// Whether or not `*` allowed headers are replaced by the requested headers when credentials are allowed
+    pub(crate) reflect_request_headers: bool,
+    /// Whether or not requests sending the literal `Origin: null` are allowed
+    pub(crate) allow_null_origin: bool,
+    /// Whether or not `Vary: Origin` is added for explicitly configured origins
+    pub(crate) emit_vary: bool,
+    /// Whether or not `*` allowed methods are expanded into a concrete list in preflight responses
+    pub(crate) expand_all_methods: bool,
+    /// Whether or not `OPTIONS` requests which aren't preflights are dispatched to the routes of the router
+    pub(crate) options_passthrough: bool,
+    /// A custom predicate which, when set, replaces matching against the allowed origins
+    pub(crate) allow_origin_predicate: Option<OriginPredicate>,
+    /// The HTTP headers exposed to scripts (lowercased and separated by commas)
+    pub(crate) exposed_headers: String,
 }
 
 impl CorsConfig {
@@ -29,15 +93,264 @@ impl CorsConfig {
         if allowed_origins.is_empty() {
             origin = NO_ORIGINS.to_string();
         }
-        let allowed_methods = allowed_methods.to_uppercase().split_whitespace().collect();
+        let allowed_methods = normalize_methods(&allowed_methods).join(",");
+        let allowed_headers = normalize_headers(&allowed_headers).join(",");
         CorsConfig {
             allowed_origins: origin,
             allowed_methods,
             allowed_headers,
             allow_credentials,
+            max_age: max_age.map(MaxAge::Cache),
+            reflect_requested_method: false,
+            disallowed_headers_status: http::StatusCode::FORBIDDEN,
+            reflect_request_headers: false,
+            allow_null_origin: false,
+            emit_vary: true,
+            expand_all_methods: false,
+            options_passthrough: false,
+            allow_origin_predicate: None,
+            exposed_headers: String::new(),
+        }
+    }
+
+    /// Creates a configuration allowing all origins, methods and headers, which is useful during development
+    ///
+    /// Credentials are not allowed, because browsers reject credentialed requests if all origins are allowed (`*`)
+    pub fn permissive() -> Self {
+        Self::new(
+            ALL_ORIGINS.to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            None,
+        )
+    }
+
+    /// Creates a configuration denying all cross-origin requests (`NO_ORIGINS`)
+    pub fn disabled() -> Self {
+        Self::new(
+            NO_ORIGINS.to_string(),
+            String::new(),
+            String::new(),
+            false,
+            None,
+        )
+    }
+
+    /// Fallible CorsConfig Constructor
+    ///
+    /// Same as [`CorsConfig::new`], but rejects allowed methods which are neither a known HTTP method nor `*`.
+    /// This catches typos (e.g. `"POTS"`) when the configuration is created
+    pub fn try_new(
+        allowed_origins: String,
+        allowed_methods: String,
+        allowed_headers: String,
+        allow_credentials: bool,
+        max_age: Option<u32>,
+    ) -> Result<Self, ContribError> {
+        if let Some(unknown) = normalize_methods(&allowed_methods)
+            .into_iter()
+            .find(|method| method != ALL_METHODS && !KNOWN_METHODS.contains(&method.as_str()))
+        {
+            return Err(ContribError::InvalidConfiguration(format!(
+                "unknown method {}",
+                unknown
+            )));
+        }
+        Ok(Self::new(
+            allowed_origins,
+            allowed_methods,
+            allowed_headers,
+            allow_credentials,
             max_age,
+        ))
+    }
+
+    /// Sets the max age of preflight responses, replacing the value provided to the constructor.
+    ///
+    /// Use `MaxAge::NoCache` to explicitly disable caching of preflight responses, or `None` to omit the header
+    pub fn with_max_age(mut self, max_age: Option<MaxAge>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Sets the max age of preflight responses to `RECOMMENDED_MAX_AGE` (600 seconds).
+    ///
+    /// Without `Access-Control-Max-Age`, browsers cache preflight responses for 5 seconds only, which causes
+    /// frequent preflights for SPAs issuing many cross-origin requests. Chromium caps the value at 600 seconds
+    pub fn with_recommended_max_age(self) -> Self {
+        self.with_max_age(Some(MaxAge::Cache(RECOMMENDED_MAX_AGE)))
+    }
+
+    /// Controls whether requests with the literal `Origin: null` are allowed (disabled by default).
+    ///
+    /// Browsers send `Origin: null` for sandboxed iframes, `file://` pages and some redirects.
+    /// Don't confuse this with `NO_ORIGINS` (which is also `"null"`): configuring `NO_ORIGINS` as allowed origins
+    /// denies all origins, whereas this setting explicitly opts into matching the `null` origin
+    /// (in addition to the configured allowed origins)
+    pub fn with_null_origin_allowed(mut self, allow: bool) -> Self {
+        self.allow_null_origin = allow;
+        self
+    }
+
+    /// Controls whether `Vary: Origin` is added to responses when origins are configured explicitly (enabled by default).
+    ///
+    /// Disable this if `Vary` is managed upstream (e.g. by a reverse proxy) to avoid duplicate or conflicting values
+    pub fn with_vary_emitted(mut self, emit: bool) -> Self {
+        self.emit_vary = emit;
+        self
+    }
+
+    /// Controls whether preflight responses list `EXPANDED_METHODS` in `Access-Control-Allow-Methods`
+    /// instead of the literal `*` when all methods are allowed (disabled by default).
+    ///
+    /// Some strict clients reject `*` and expect a concrete list of methods
+    pub fn with_expanded_all_methods(mut self, expand: bool) -> Self {
+        self.expand_all_methods = expand;
+        self
+    }
+
+    /// Controls whether `CorsRouter::handle_with_cors` passes `OPTIONS` requests without
+    /// `Access-Control-Request-Method` (which aren't preflights) to the routes of the router (disabled by default).
+    ///
+    /// Enable this if the API exposes its own `OPTIONS` handlers (e.g. to describe resources). Preflights are
+    /// still answered by `handle_with_cors` itself
+    pub fn with_options_passthrough(mut self, passthrough: bool) -> Self {
+        self.options_passthrough = passthrough;
+        self
+    }
+
+    /// Sets a custom predicate deciding whether or not an origin is allowed.
+    ///
+    /// The predicate replaces matching against the configured allowed origins and receives the trimmed request
+    /// origin. Allowed origins are always reflected in `Access-Control-Allow-Origin`. Use this for policies
+    /// which can't be expressed as a list of origins (e.g. an allow-list loaded from a database).
+    /// The literal `Origin: null` is still controlled by [`CorsConfig::with_null_origin_allowed`]
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_contrib_http::cors::CorsConfig;
+    ///
+    /// let cfg = CorsConfig::disabled()
+    ///     .with_origin_predicate(|origin| origin.ends_with(".internal"));
+    /// ```
+    pub fn with_origin_predicate(
+        mut self,
+        predicate: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.allow_origin_predicate = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Sets the HTTP headers (separated by commas) exposed to scripts using `Access-Control-Expose-Headers`.
+    ///
+    /// The header is added to responses of allowed (non-preflight) cross-origin requests. Browsers only accept
+    /// `*` for requests without credentials, see [`CorsConfig::expose_all_safe_headers`]
+    pub fn with_exposed_headers(mut self, exposed_headers: String) -> Self {
+        self.exposed_headers = normalize_headers(&exposed_headers).join(",");
+        self
+    }
+
+    /// Exposes as many headers as browsers accept for the configured credentials mode.
+    ///
+    /// Without credentials, `*` is exposed. With credentials, browsers treat `*` as a literal header name,
+    /// so the CORS-safelisted response headers plus the provided headers are exposed instead.
+    /// Call this after configuring credentials (it respects the current setting)
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_contrib_http::cors::CorsConfig;
+    ///
+    /// let cfg = CorsConfig::new(
+    ///     "https://app.example.com".to_string(),
+    ///     "GET".to_string(),
+    ///     String::new(),
+    ///     true,
+    ///     None,
+    /// )
+    /// .expose_all_safe_headers(&["x-request-id", "etag"]);
+    /// ```
+    pub fn expose_all_safe_headers(self, additional: &[&str]) -> Self {
+        if !self.allow_credentials {
+            return self.with_exposed_headers(ALL_HEADERS.to_string());
+        }
+        let exposed_headers = SAFELISTED_RESPONSE_HEADERS
+            .iter()
+            .chain(additional.iter())
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(",");
+        self.with_exposed_headers(exposed_headers)
+    }
+
+    /// Determines if the provided (non-empty) request origin is allowed
+    ///
+    /// Malformed origins are never allowed, which prevents reflecting them in response headers
+    pub(crate) fn allows_origin(&self, origin: &str) -> bool {
+        if normalize_origin(origin) == NULL_ORIGIN {
+            return self.allow_null_origin;
+        }
+        if !is_well_formed_origin(origin) {
+            return false;
+        }
+        if let Some(predicate) = &self.allow_origin_predicate {
+            return predicate(origin.trim());
+        }
+        is_origin_allowed(&self.allowed_origins, origin)
+    }
+
+    /// Allows any header, while keeping credentialed requests working.
+    ///
+    /// Browsers don't accept `Access-Control-Allow-Headers: *` if credentials are allowed. In this mode,
+    /// preflight responses of credentialed configurations reflect the requested headers
+    /// (`Access-Control-Request-Headers`) instead of responding with `*`
+    pub fn allow_any_header_but_reflect(mut self) -> Self {
+        self.allowed_headers = ALL_HEADERS.to_string();
+        self.reflect_request_headers = true;
+        self
+    }
+
+    /// Controls whether preflight responses echo only the requested method (`Access-Control-Request-Method`)
+    /// in `Access-Control-Allow-Methods` instead of the whole list of allowed methods.
+    ///
+    /// This avoids disclosing all allowed methods to clients. Disabled by default
+    pub fn with_reflected_request_method(mut self, reflect: bool) -> Self {
+        self.reflect_requested_method = reflect;
+        self
+    }
+
+    /// Sets the status code used to reject preflights requesting headers (`Access-Control-Request-Headers`)
+    /// which are not allowed. Defaults to `403 Forbidden`
+    pub fn with_disallowed_headers_status(mut self, status: http::StatusCode) -> Self {
+        self.disallowed_headers_status = status;
+        self
+    }
+}
+
+/// Splits, trims and uppercases a comma-separated list of methods, removing empty entries and duplicates
+fn normalize_methods(allowed_methods: &str) -> Vec<String> {
+    let mut methods: Vec<String> = vec![];
+    for method in allowed_methods.split(',') {
+        let method = method.trim().to_uppercase();
+        if !method.is_empty() && !methods.contains(&method) {
+            methods.push(method);
         }
     }
+    methods
+}
+
+/// Splits, trims and lowercases a comma-separated list of header names, removing empty entries and duplicates
+///
+/// Header names are case-insensitive, so the normalized list can be compared against requested headers directly
+fn normalize_headers(allowed_headers: &str) -> Vec<String> {
+    let mut headers: Vec<String> = vec![];
+    for header in allowed_headers.split(',') {
+        let header = header.trim().to_lowercase();
+        if !header.is_empty() && !headers.contains(&header) {
+            headers.push(header);
+        }
+    }
+    headers
 }
 
 impl Debug for CorsConfig {
@@ -48,10 +361,60 @@ impl Debug for CorsConfig {
             .field("allowed_headers", &self.allowed_headers)
             .field("allow_credentials", &self.allow_credentials)
             .field("max_age", &self.max_age)
+            .field("reflect_requested_method", &self.reflect_requested_method)
+            .field("disallowed_headers_status", &self.disallowed_headers_status)
+            .field("reflect_request_headers", &self.reflect_request_headers)
+            .field("allow_null_origin", &self.allow_null_origin)
+            .field("emit_vary", &self.emit_vary)
+            .field("expand_all_methods", &self.expand_all_methods)
+            .field("options_passthrough", &self.options_passthrough)
+            .field(
+                "allow_origin_predicate",
+                &self.allow_origin_predicate.as_ref().map(|_| "<predicate>"),
+            )
+            .field("exposed_headers", &self.exposed_headers)
             .finish()
     }
 }
 
+/// Formats the effective CORS policy as single line (e.g. for log lines)
+///
+/// `origins=[http://localhost:4200] methods=[GET, POST] headers=[*] credentials=true max-age=600`
+///
+/// A configuration allowing no origins (`NO_ORIGINS`) is printed as `origins=[]`
+impl Display for CorsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |value: &str| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        };
+        let origins = if self.allow_origin_predicate.is_some() {
+            "<predicate>".to_string()
+        } else if self.allowed_origins == NO_ORIGINS {
+            // NO_ORIGINS is the literal "null", which would read like the null origin being allowed
+            String::new()
+        } else {
+            list(&self.allowed_origins)
+        };
+        write!(
+            f,
+            "origins=[{}] methods=[{}] headers=[{}] credentials={} max-age=",
+            origins,
+            list(&self.allowed_methods),
+            list(&self.allowed_headers),
+            self.allow_credentials
+        )?;
+        match self.max_age {
+            Some(max_age) => write!(f, "{}", max_age),
+            None => write!(f, "none"),
+        }
+    }
+}
+
 impl Clone for CorsConfig {
     fn clone(&self) -> Self {
         Self {
@@ -60,6 +423,15 @@ impl Clone for CorsConfig {
             allowed_headers: self.allowed_headers.clone(),
             allow_credentials: self.allow_credentials,
             max_age: self.max_age,
+            reflect_requested_method: self.reflect_requested_method,
+            disallowed_headers_status: self.disallowed_headers_status,
+            reflect_request_headers: self.reflect_request_headers,
+            allow_null_origin: self.allow_null_origin,
+            emit_vary: self.emit_vary,
+            expand_all_methods: self.expand_all_methods,
+            options_passthrough: self.options_passthrough,
+            allow_origin_predicate: self.allow_origin_predicate.clone(),
+            exposed_headers: self.exposed_headers.clone(),
         }
     }
 }
@@ -67,7 +439,7 @@ impl Clone for CorsConfig {
 #[cfg(test)]
 mod tests {
 
-    use crate::cors::{ALL_HEADERS, ALL_METHODS, NO_ORIGINS};
+    use crate::cors::{ALL_HEADERS, ALL_METHODS, ALL_ORIGINS, NO_ORIGINS, NULL_ORIGIN};
 
     use super::*;
 
@@ -83,4 +455,177 @@ mod tests {
         );
         assert_eq!(sut.allowed_origins, NO_ORIGINS);
     }
+
+    #[test]
+    fn cors_config_should_match_null_origin_only_if_explicitly_allowed() {
+        let test_data = vec![ALL_ORIGINS, NO_ORIGINS, "http://localhost:4200"];
+        for allowed_origins in test_data {
+            let sut = CorsConfig::new(
+                allowed_origins.to_string(),
+                ALL_METHODS.to_string(),
+                ALL_HEADERS.to_string(),
+                false,
+                None,
+            );
+            assert!(!sut.allows_origin(NULL_ORIGIN), "{}", allowed_origins);
+
+            let sut = sut.with_null_origin_allowed(true);
+            assert!(sut.allows_origin(NULL_ORIGIN), "{}", allowed_origins);
+            assert!(sut.allows_origin("NULL"), "{}", allowed_origins);
+        }
+    }
+
+    #[test]
+    fn cors_config_should_deduplicate_methods() {
+        let sut = CorsConfig::new(
+            ALL_ORIGINS.to_string(),
+            "post, POST,get".to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            None,
+        );
+        assert_eq!(sut.allowed_methods, "POST,GET");
+    }
+
+    #[test]
+    fn cors_config_should_normalize_headers() {
+        let sut = CorsConfig::new(
+            ALL_ORIGINS.to_string(),
+            ALL_METHODS.to_string(),
+            "Content-Type, X-Custom,content-type, ".to_string(),
+            false,
+            None,
+        );
+        assert_eq!(sut.allowed_headers, "content-type,x-custom");
+    }
+
+    #[test]
+    fn expose_all_safe_headers_should_respect_credentials() {
+        let sut = CorsConfig::permissive().expose_all_safe_headers(&["X-Request-Id"]);
+        assert_eq!(sut.exposed_headers, ALL_HEADERS);
+
+        let sut = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            None,
+        )
+        .expose_all_safe_headers(&["X-Request-Id", "content-type"]);
+        assert_eq!(
+            sut.exposed_headers,
+            "cache-control,content-language,content-length,content-type,expires,last-modified,pragma,x-request-id"
+        );
+    }
+
+    #[test]
+    fn cors_config_should_display_effective_policy() {
+        let sut = CorsConfig::new(
+            "http://localhost:4200,http://localhost:8080".to_string(),
+            "get, post".to_string(),
+            "Content-Type, Authorization".to_string(),
+            true,
+            Some(600),
+        );
+        assert_eq!(
+            sut.to_string(),
+            "origins=[http://localhost:4200, http://localhost:8080] methods=[GET, POST] headers=[content-type, authorization] credentials=true max-age=600"
+        );
+
+        let sut = CorsConfig::disabled();
+        assert_eq!(
+            sut.to_string(),
+            "origins=[] methods=[] headers=[] credentials=false max-age=none"
+        );
+
+        let sut = CorsConfig::permissive().with_origin_predicate(|_| true);
+        assert_eq!(
+            sut.to_string(),
+            "origins=[<predicate>] methods=[*] headers=[*] credentials=false max-age=none"
+        );
+    }
+
+    #[test]
+    fn cors_config_try_new_should_reject_unknown_methods() {
+        let sut = CorsConfig::try_new(
+            ALL_ORIGINS.to_string(),
+            "GET, FOO".to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            None,
+        );
+        assert_eq!(
+            sut.unwrap_err(),
+            ContribError::InvalidConfiguration("unknown method FOO".to_string())
+        );
+
+        let test_data = vec![ALL_METHODS, "post, POST", "GET,PATCH"];
+        for methods in test_data {
+            let sut = CorsConfig::try_new(
+                ALL_ORIGINS.to_string(),
+                methods.to_string(),
+                ALL_HEADERS.to_string(),
+                false,
+                None,
+            );
+            assert!(sut.is_ok(), "{}", methods);
+        }
+    }
+
+    #[test]
+    fn permissive_should_allow_any_origin_without_credentials() {
+        let sut = CorsConfig::permissive();
+        assert!(sut.allows_origin("https://random.example.com"));
+        assert!(!sut.allow_credentials);
+        assert_eq!(sut.allowed_methods, ALL_METHODS);
+        assert_eq!(sut.allowed_headers, ALL_HEADERS);
+    }
+
+    #[test]
+    fn disabled_should_deny_all_origins() {
+        let sut = CorsConfig::disabled();
+        let test_data = vec![
+            "https://random.example.com",
+            "http://localhost:4200",
+            NULL_ORIGIN,
+        ];
+        for origin in test_data {
+            assert!(!sut.allows_origin(origin), "{}", origin);
+        }
+    }
+
+    #[test]
+    fn max_age_should_format_seconds_and_no_cache() {
+        assert_eq!(MaxAge::Cache(600).to_string(), "600");
+        assert_eq!(MaxAge::NoCache.to_string(), "-1");
+
+        let sut = CorsConfig::new(
+            ALL_ORIGINS.to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            Some(300),
+        );
+        assert_eq!(sut.max_age, Some(MaxAge::Cache(300)));
+        let sut = sut.with_max_age(Some(MaxAge::NoCache));
+        assert_eq!(sut.max_age, Some(MaxAge::NoCache));
+    }
+
+    #[test]
+    fn cors_config_should_use_origin_predicate_if_set() {
+        let sut =
+            CorsConfig::disabled().with_origin_predicate(|origin| origin.ends_with(".internal"));
+        let test_data = vec![
+            ("https://api.internal", true),
+            (" https://app.internal ", true),
+            ("https://api.internal.evil.com", false),
+            ("https://example.com", false),
+            (NULL_ORIGIN, false),
+        ];
+        for (origin, expected) in test_data {
+            assert_eq!(sut.allows_origin(origin), expected, "{}", origin);
+            assert_eq!(sut.clone().allows_origin(origin), expected, "{}", origin);
+        }
+        assert!(format!("{:?}", sut).contains("<predicate>"));
+    }
 }
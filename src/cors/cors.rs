@@ -4,20 +4,31 @@ use super::CorsConfig;
 
 /// Constant for allowing all HTTP methods in CORS
 pub const ALL_METHODS: &str = "*";
+/// The methods listed instead of `*` if `CorsConfig::with_expanded_all_methods` is enabled
+pub const EXPANDED_METHODS: &str = "GET, POST, PUT, PATCH, DELETE, OPTIONS";
 /// Constant for allowing all HTTP headers in CORS
 pub const ALL_HEADERS: &str = "*";
 /// Constant for allowing all origins in CORS
 pub const ALL_ORIGINS: &str = "*";
 /// Constant for allowing no origins in CORS
 pub const NO_ORIGINS: &str = "null";
+/// The literal origin sent by browsers for opaque origins (e.g. sandboxed iframes or `file://` pages)
+///
+/// See `CorsConfig::with_null_origin_allowed`
+pub const NULL_ORIGIN: &str = "null";
 
-fn is_preflight(m: &Method, origin: &str) -> bool {
-    m == &Method::Options && !origin.is_empty()
+/// Determines if a request is a CORS preflight request.
+///
+/// A preflight request uses `OPTIONS` and carries both `Origin` and `Access-Control-Request-Method`
+pub(crate) fn is_preflight(m: &Method, origin: &str, requested_method: &str) -> bool {
+    m == &Method::Options && !origin.trim().is_empty() && !requested_method.trim().is_empty()
 }
 
 pub(crate) fn build_cors_headers(
     request_method: &Method,
     request_origin: String,
+    requested_method: &str,
+    requested_headers: &str,
     cors_config: &CorsConfig,
 ) -> Vec<(String, String)> {
     let mut headers: Vec<(String, String)> = vec![];
@@ -27,10 +38,19 @@ pub(crate) fn build_cors_headers(
     }
 
     // if origin is not allowed, return no cors headers
-    if is_origin_allowed(&cors_config.allowed_origins, &request_origin) {
+    let origin_allowed = cors_config.allows_origin(&request_origin);
+    if origin_allowed {
         headers.push((
             http::header::ACCESS_CONTROL_ALLOW_ORIGIN.to_string(),
-            get_origin_header_value(&cors_config.allowed_origins, &request_origin),
+            if cors_config.allow_origin_predicate.is_some() {
+                request_origin.trim().to_string()
+            } else {
+                get_origin_header_value(
+                    &cors_config.allowed_origins,
+                    &request_origin,
+                    cors_config.allow_credentials,
+                )
+            },
         ));
 
         headers.push((
@@ -39,28 +59,57 @@ pub(crate) fn build_cors_headers(
         ));
     }
 
-    if cors_config.allowed_origins != ALL_ORIGINS && cors_config.allowed_origins != NO_ORIGINS {
+    // the allowed origin depends on the request origin, unless all origins are allowed without credentials
+    let reflects_origin = cors_config.allow_origin_predicate.is_some()
+        || (cors_config.allowed_origins != NO_ORIGINS
+            && (cors_config.allowed_origins != ALL_ORIGINS || cors_config.allow_credentials));
+    if cors_config.emit_vary && reflects_origin {
         headers.push((http::header::VARY.to_string(), "Origin".to_string()));
     }
 
-    if !is_preflight(request_method, &request_origin) {
+    if !is_preflight(request_method, &request_origin, requested_method) {
+        if origin_allowed && !cors_config.exposed_headers.is_empty() {
+            headers.push((
+                http::header::ACCESS_CONTROL_EXPOSE_HEADERS.to_string(),
+                cors_config.exposed_headers.clone(),
+            ));
+        }
         return headers;
     }
 
-    if cors_config.max_age.is_some() {
+    if let Some(max_age) = cors_config.max_age {
         headers.push((
             http::header::ACCESS_CONTROL_MAX_AGE.to_string(),
-            format!("{}", cors_config.max_age.unwrap()),
+            max_age.to_string(),
         ));
     }
+    let allowed_methods = if cors_config.reflect_requested_method
+        && is_method_allowed(&cors_config.allowed_methods, requested_method)
+    {
+        requested_method.trim().to_uppercase()
+    } else if cors_config.expand_all_methods && cors_config.allowed_methods == ALL_METHODS {
+        EXPANDED_METHODS.to_string()
+    } else {
+        cors_config.allowed_methods.clone()
+    };
     headers.push((
         http::header::ACCESS_CONTROL_ALLOW_METHODS.to_string(),
-        cors_config.allowed_methods.clone(),
-    ));
-    headers.push((
-        http::header::ACCESS_CONTROL_ALLOW_HEADERS.to_string(),
-        cors_config.allowed_headers.clone(),
+        allowed_methods,
     ));
+    let allowed_headers = if cors_config.reflect_request_headers
+        && cors_config.allow_credentials
+        && cors_config.allowed_headers == ALL_HEADERS
+    {
+        requested_headers.trim().to_string()
+    } else {
+        cors_config.allowed_headers.clone()
+    };
+    if !allowed_headers.is_empty() {
+        headers.push((
+            http::header::ACCESS_CONTROL_ALLOW_HEADERS.to_string(),
+            allowed_headers,
+        ));
+    }
     headers
 }
 
@@ -89,6 +138,22 @@ pub(crate) fn is_method_allowed(allowed_methods: &str, requested_methods: &str)
     true
 }
 
+pub(crate) fn are_headers_allowed(allowed_headers: &str, requested_headers: &str) -> bool {
+    if allowed_headers.trim() == ALL_HEADERS {
+        return true;
+    }
+
+    let allowed_headers: Vec<String> = allowed_headers
+        .split(',')
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+    requested_headers
+        .split(',')
+        .map(|header| header.trim().to_lowercase())
+        .filter(|header| !header.is_empty())
+        .all(|header| allowed_headers.contains(&header))
+}
+
 pub(crate) fn is_origin_allowed(allowed_origins: &str, origin: &str) -> bool {
     if allowed_origins == NO_ORIGINS {
         return false;
@@ -104,24 +169,231 @@ pub(crate) fn is_origin_allowed(allowed_origins: &str, origin: &str) -> bool {
         .collect::<String>();
     let allowed_origins: Vec<&str> = allowed_origins.split(',').collect();
 
-    allowed_origins.contains(&origin.to_lowercase().trim())
+    allowed_origins.contains(&normalize_origin(origin).as_str())
 }
 
-pub(crate) fn get_origin_header_value(allowed_origins: &str, request_origin: &str) -> String {
-    if allowed_origins == ALL_ORIGINS {
-        return request_origin.to_string();
+/// Determines if an origin is well-formed (`scheme://host[:port]`), so it can safely be reflected in a response header
+///
+/// Hosts may be domain names, IPv4 addresses or bracketed IPv6 addresses. Origins carrying a path, userinfo,
+/// whitespace or control characters (e.g. `\r\n`) are rejected
+pub(crate) fn is_well_formed_origin(origin: &str) -> bool {
+    let Some((scheme, authority)) = origin.trim().split_once("://") else {
+        return false;
+    };
+    let scheme_is_valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !scheme_is_valid {
+        return false;
     }
-    if allowed_origins.contains(request_origin) {
-        return request_origin.to_string();
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(rest) => {
+            let Some((ipv6, port)) = rest.split_once(']') else {
+                return false;
+            };
+            if ipv6.is_empty() || !ipv6.chars().all(|c| c.is_ascii_hexdigit() || c == ':') {
+                return false;
+            }
+            (ipv6, port)
+        }
+        None => match authority.find(':') {
+            Some(idx) => authority.split_at(idx),
+            None => (authority, ""),
+        },
+    };
+    let host_is_valid = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ':'));
+    let port_is_valid = port.is_empty()
+        || port
+            .strip_prefix(':')
+            .is_some_and(|port| port.parse::<u16>().is_ok());
+    host_is_valid && port_is_valid
+}
+
+/// Normalizes an origin for comparison (trimmed and lowercased)
+pub(crate) fn normalize_origin(origin: &str) -> String {
+    origin.trim().to_lowercase()
+}
+
+/// Returns the value of `Access-Control-Allow-Origin` for a request origin accepted by `CorsConfig::allows_origin`
+///
+/// If all origins are allowed, `*` is returned. Browsers reject `*` for credentialed requests,
+/// which is why the (trimmed) request origin is reflected if credentials are allowed. Explicitly configured
+/// origins are matched case-insensitively, so the request origin is reflected as well
+pub(crate) fn get_origin_header_value(
+    allowed_origins: &str,
+    request_origin: &str,
+    allow_credentials: bool,
+) -> String {
+    if allowed_origins == ALL_ORIGINS && !allow_credentials {
+        return ALL_ORIGINS.to_string();
     }
-    NO_ORIGINS.to_string()
+    request_origin.trim().to_string()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::cors::{is_method_allowed, is_origin_allowed};
+    use spin_sdk::http::Method;
+
+    use crate::cors::{
+        are_headers_allowed, is_method_allowed, is_origin_allowed, is_well_formed_origin,
+        CorsConfig, MaxAge,
+    };
+
+    use super::{
+        build_cors_headers, ALL_HEADERS, ALL_METHODS, ALL_ORIGINS, EXPANDED_METHODS, NO_ORIGINS,
+    };
+
+    #[test]
+    fn build_cors_headers_should_emit_max_age() {
+        let test_data = vec![(MaxAge::Cache(600), "600"), (MaxAge::NoCache, "-1")];
+        for (max_age, expected) in test_data {
+            let cfg = CorsConfig::permissive().with_max_age(Some(max_age));
+            let sut = build_cors_headers(
+                &Method::Options,
+                "http://localhost:4200".to_string(),
+                "PUT",
+                "",
+                &cfg,
+            );
+
+            let actual = sut
+                .iter()
+                .find(|(name, _)| name == http::header::ACCESS_CONTROL_MAX_AGE.as_str())
+                .map(|(_, value)| value.as_str());
+            assert_eq!(actual, Some(expected));
+        }
+    }
+
+    #[test]
+    fn build_cors_headers_should_emit_recommended_max_age() {
+        let cfg = CorsConfig::permissive().with_recommended_max_age();
+        let sut = build_cors_headers(
+            &Method::Options,
+            "http://localhost:4200".to_string(),
+            "PUT",
+            "",
+            &cfg,
+        );
+        assert!(sut.contains(&(
+            http::header::ACCESS_CONTROL_MAX_AGE.to_string(),
+            "600".to_string()
+        )));
+
+        let sut = build_cors_headers(
+            &Method::Options,
+            "http://localhost:4200".to_string(),
+            "PUT",
+            "",
+            &CorsConfig::permissive(),
+        );
+        assert!(!sut
+            .iter()
+            .any(|(name, _)| name == http::header::ACCESS_CONTROL_MAX_AGE.as_str()));
+    }
+
+    #[test]
+    fn build_cors_headers_should_emit_exposed_headers_for_actual_requests() {
+        let test_data = vec![
+            (false, ALL_HEADERS),
+            (
+                true,
+                "cache-control,content-language,content-length,content-type,expires,last-modified,pragma,x-request-id",
+            ),
+        ];
+        for (allow_credentials, expected) in test_data {
+            let cfg = CorsConfig::new(
+                ALL_ORIGINS.to_string(),
+                ALL_METHODS.to_string(),
+                ALL_HEADERS.to_string(),
+                allow_credentials,
+                None,
+            )
+            .expose_all_safe_headers(&["x-request-id"]);
+            let sut = build_cors_headers(
+                &Method::Get,
+                "http://localhost:4200".to_string(),
+                "",
+                "",
+                &cfg,
+            );
+            assert!(sut.contains(&(
+                http::header::ACCESS_CONTROL_EXPOSE_HEADERS.to_string(),
+                expected.to_string()
+            )));
+
+            let sut = build_cors_headers(
+                &Method::Options,
+                "http://localhost:4200".to_string(),
+                "GET",
+                "",
+                &cfg,
+            );
+            assert!(!sut
+                .iter()
+                .any(|(name, _)| name == http::header::ACCESS_CONTROL_EXPOSE_HEADERS.as_str()));
+        }
+    }
+
+    #[test]
+    fn build_cors_headers_should_expand_all_methods_if_configured() {
+        let test_data = vec![(false, ALL_METHODS), (true, EXPANDED_METHODS)];
+        for (expand, expected) in test_data {
+            let cfg = CorsConfig::new(
+                ALL_ORIGINS.to_string(),
+                ALL_METHODS.to_string(),
+                ALL_HEADERS.to_string(),
+                false,
+                None,
+            )
+            .with_expanded_all_methods(expand);
+            let sut = build_cors_headers(
+                &Method::Options,
+                "http://localhost:4200".to_string(),
+                "PUT",
+                "",
+                &cfg,
+            );
+
+            let actual = sut
+                .iter()
+                .find(|(name, _)| name == http::header::ACCESS_CONTROL_ALLOW_METHODS.as_str())
+                .map(|(_, value)| value.as_str());
+            assert_eq!(actual, Some(expected));
+        }
+    }
 
-    use super::{ALL_ORIGINS, NO_ORIGINS};
+    #[test]
+    fn build_cors_headers_should_reflect_origins_allowed_by_predicate() {
+        let cfg =
+            CorsConfig::disabled().with_origin_predicate(|origin| origin.ends_with(".internal"));
+        let sut = build_cors_headers(
+            &Method::Get,
+            "https://api.internal".to_string(),
+            "",
+            "",
+            &cfg,
+        );
+        assert!(sut.contains(&(
+            http::header::ACCESS_CONTROL_ALLOW_ORIGIN.to_string(),
+            "https://api.internal".to_string()
+        )));
+        assert!(sut.contains(&(http::header::VARY.to_string(), "Origin".to_string())));
+
+        let sut = build_cors_headers(
+            &Method::Get,
+            "https://example.com".to_string(),
+            "",
+            "",
+            &cfg,
+        );
+        assert!(!sut
+            .iter()
+            .any(|(name, _)| name == http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str()));
+    }
 
     #[test]
     fn is_method_allowed_tests() {
@@ -151,6 +423,117 @@ mod tests {
         }
     }
 
+    #[test]
+    fn are_headers_allowed_tests() {
+        let test_data = vec![
+            ("*", "X-Custom", true),
+            ("Content-Type", "", true),
+            ("Content-Type", "Content-Type", true),
+            (
+                "Content-Type, Authorization",
+                "authorization,content-type",
+                true,
+            ),
+            ("Content-Type", "Content-Type, X-Custom", false),
+            ("", "X-Custom", false),
+        ];
+
+        for (allowed, requested, expected) in test_data {
+            assert_eq!(
+                are_headers_allowed(allowed, requested),
+                expected,
+                "Allowed were: {}, Requested were: {}",
+                allowed,
+                requested
+            );
+        }
+    }
+
+    #[test]
+    fn is_well_formed_origin_tests() {
+        let test_data = vec![
+            ("http://localhost:4200", true),
+            ("https://example.com", true),
+            ("https://sub.example.com:443", true),
+            ("http://[2001:db8::1]:8080", true),
+            ("http://127.0.0.1", true),
+            ("http://localhost:4200\r\nSet-Cookie: a=b", false),
+            ("http://local\nhost", false),
+            ("https://example.com/path", false),
+            ("https://user@example.com", false),
+            ("https://example.com:99999", false),
+            ("https://example.com:", false),
+            ("https://", false),
+            ("example.com", false),
+            ("1http://example.com", false),
+            ("http://[zz::1]", false),
+        ];
+        for (origin, expected) in test_data {
+            assert_eq!(is_well_formed_origin(origin), expected, "{:?}", origin);
+        }
+    }
+
+    #[test]
+    fn build_cors_headers_should_not_reflect_malformed_origins() {
+        let cfg = CorsConfig::new(
+            ALL_ORIGINS.to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            None,
+        );
+        let sut = build_cors_headers(
+            &Method::Get,
+            "http://localhost:4200\r\nSet-Cookie: a=b".to_string(),
+            "",
+            "",
+            &cfg,
+        );
+        assert!(!sut
+            .iter()
+            .any(|(name, _)| name == http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str()));
+    }
+
+    #[test]
+    fn build_cors_headers_should_reflect_allowed_origins_regardless_of_case_and_whitespace() {
+        let test_data = vec![
+            (
+                "http://localhost:4200",
+                false,
+                "HTTP://LocalHost:4200",
+                "HTTP://LocalHost:4200",
+            ),
+            (
+                "http://localhost:4200",
+                false,
+                " http://localhost:4200 ",
+                "http://localhost:4200",
+            ),
+            (
+                ALL_ORIGINS,
+                true,
+                " http://localhost:4200 ",
+                "http://localhost:4200",
+            ),
+            (ALL_ORIGINS, false, "http://localhost:4200", "*"),
+        ];
+        for (allowed, credentials, origin, expected) in test_data {
+            let cfg = CorsConfig::new(
+                allowed.to_string(),
+                ALL_METHODS.to_string(),
+                ALL_HEADERS.to_string(),
+                credentials,
+                None,
+            );
+            let sut = build_cors_headers(&Method::Get, origin.to_string(), "", "", &cfg);
+            let actual = sut
+                .iter()
+                .find(|(name, _)| name == http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
+                .map(|(_, value)| value.as_str());
+            assert_eq!(actual, Some(expected), "Origin: {:?}", origin);
+        }
+    }
+
     #[test]
     fn is_origin_allowed_tests() {
         let test_data = vec![
@@ -1,16 +1,48 @@
 use spin_sdk::http::{Method, Response, ResponseBuilder};
 
-use super::{build_cors_headers, is_origin_allowed, CorsConfig, ALL_ORIGINS};
+use crate::cookies::Cookie;
+use crate::response::ContribResponseBuilder;
+
+use super::{build_cors_headers, CorsConfig, ALL_ORIGINS};
 
 /// Trait to add CORS capabilities
 pub trait CorsResponseBuilder {
     /// Build an HTTP response with CORS headers
+    ///
+    /// Preflight headers (`Access-Control-Allow-Methods`, `Access-Control-Allow-Headers` and `Access-Control-Max-Age`)
+    /// are never added, also not for `Method::Options`, because the requested method and headers are unknown here.
+    /// Use `preflight_response` (or `CorsRouter::handle_with_cors`) to answer preflight requests.
+    ///
+    /// If the request origin is not allowed, a clean `403 Forbidden` without body and without `Access-Control-*`
+    /// headers is returned instead. It only carries `Vary: Origin` (if configured), so caches don't serve it to other origins
     fn build_with_cors(
         &mut self,
         request_method: &Method,
         request_origin: String,
         cors_config: &CorsConfig,
     ) -> Response;
+
+    /// Build an HTTP response with CORS headers, expressing a missing `Origin` header as `None`
+    ///
+    /// Same as `build_with_cors`. `None` (and an empty origin) results in a response without CORS headers
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::{Request, Response, ResponseBuilder};
+    /// use spin_contrib_http::cors::{CorsConfig, CorsResponseBuilder};
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// fn handle(req: Request) -> Response {
+    ///   let cfg = CorsConfig::permissive();
+    ///   ResponseBuilder::new(200).build_with_cors_opt(req.method(), req.origin().as_deref(), &cfg)
+    /// }
+    /// ```
+    fn build_with_cors_opt(
+        &mut self,
+        request_method: &Method,
+        request_origin: Option<&str>,
+        cors_config: &CorsConfig,
+    ) -> Response;
 }
 
 impl CorsResponseBuilder for ResponseBuilder {
@@ -20,24 +52,81 @@ impl CorsResponseBuilder for ResponseBuilder {
         request_origin: String,
         cors_config: &CorsConfig,
     ) -> Response {
-        if !request_origin.is_empty()
-            && cors_config.allowed_origins != ALL_ORIGINS
-            && !is_origin_allowed(&cors_config.allowed_origins, &request_origin)
-        {
-            self.status(403);
-            self.body(());
+        if !request_origin.is_empty() && !cors_config.allows_origin(&request_origin) {
+            // for disallowed origins, build_cors_headers only returns Vary
+            let mut forbidden = ResponseBuilder::new(http::StatusCode::FORBIDDEN);
+            for (name, value) in
+                build_cors_headers(request_method, request_origin, "", "", cors_config)
+            {
+                forbidden.header(name, value);
+            }
+            return forbidden.body(()).build();
+        }
+
+        let headers = build_cors_headers(request_method, request_origin, "", "", cors_config);
+        for (name, value) in headers {
+            if name == http::header::VARY.as_str() {
+                self.with_vary(&[&value]);
+            } else {
+                self.header(name, value);
+            }
         }
+        self.build()
+    }
 
-        let headers = build_cors_headers(request_method, request_origin, cors_config);
-        self.headers(headers).build()
+    fn build_with_cors_opt(
+        &mut self,
+        request_method: &Method,
+        request_origin: Option<&str>,
+        cors_config: &CorsConfig,
+    ) -> Response {
+        self.build_with_cors(
+            request_method,
+            request_origin.unwrap_or_default().to_string(),
+            cors_config,
+        )
     }
 }
 
+/// Builds a CORS-enabled `200 OK` response carrying exactly one `Set-Cookie` header (e.g. a session cookie after login).
+///
+/// If the request origin is not allowed, or the configuration combines credentials with a wildcard origin
+/// (which would allow any site to obtain a session), a `403 Forbidden` without `Set-Cookie` is returned
+///
+/// # Arguments
+///
+/// * `request_method` - The method of the incoming request
+/// * `request_origin` - The value of the `Origin` header of the incoming request (may be empty)
+/// * `cors_config` - The CORS configuration
+/// * `session_cookie` - The cookie to set
+pub fn login_response(
+    request_method: &Method,
+    request_origin: String,
+    cors_config: &CorsConfig,
+    session_cookie: Cookie,
+) -> Response {
+    if cors_config.allow_credentials && cors_config.allowed_origins == ALL_ORIGINS {
+        return Response::new(http::StatusCode::FORBIDDEN, ());
+    }
+    if !request_origin.is_empty() && !cors_config.allows_origin(&request_origin) {
+        return Response::new(http::StatusCode::FORBIDDEN, ());
+    }
+    ResponseBuilder::new(http::StatusCode::OK)
+        .header(
+            http::header::SET_COOKIE.as_str(),
+            session_cookie.to_string(),
+        )
+        .build_with_cors(request_method, request_origin, cors_config)
+}
+
 #[cfg(test)]
 mod tests {
     use spin_sdk::http::{HeaderValue, Method, RequestBuilder, ResponseBuilder};
 
-    use crate::cors::{CorsConfig, CorsResponseBuilder, ALL_HEADERS, ALL_METHODS, ALL_ORIGINS};
+    use crate::cookies::{Cookie, SameSite};
+    use crate::cors::{
+        login_response, CorsConfig, CorsResponseBuilder, ALL_HEADERS, ALL_METHODS, ALL_ORIGINS,
+    };
 
     #[test]
     fn vary_header_should_be_set_when_origins_are_set_explicitly() {
@@ -52,13 +141,13 @@ mod tests {
         ];
 
         for td in test_data {
-            let cfg = CorsConfig {
-                allowed_origins: td.to_string(),
-                allowed_headers: ALL_HEADERS.to_string(),
-                allowed_methods: ALL_METHODS.to_string(),
-                allow_credentials: true,
-                max_age: None,
-            };
+            let cfg = CorsConfig::new(
+                td.to_string(),
+                ALL_METHODS.to_string(),
+                ALL_HEADERS.to_string(),
+                true,
+                None,
+            );
             let request_origin = req
                 .header(http::header::ORIGIN.as_str())
                 .unwrap_or(&HeaderValue::string(String::default()))
@@ -68,7 +157,7 @@ mod tests {
             let sut = ResponseBuilder::new(200).build_with_cors(req.method(), request_origin, &cfg);
 
             let vary_header = sut.header(http::header::VARY.as_str());
-            assert_eq!(vary_header.is_some(), true);
+            assert!(vary_header.is_some());
             assert_eq!(vary_header.unwrap().as_str().unwrap(), "Origin");
         }
     }
@@ -80,13 +169,13 @@ mod tests {
             .body(())
             .build();
 
-        let cfg = CorsConfig {
-            allowed_origins: ALL_ORIGINS.to_string(),
-            allowed_headers: ALL_HEADERS.to_string(),
-            allowed_methods: ALL_METHODS.to_string(),
-            allow_credentials: true,
-            max_age: None,
-        };
+        let cfg = CorsConfig::new(
+            ALL_ORIGINS.to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            None,
+        );
 
         let request_origin = req
             .header(http::header::ORIGIN.as_str())
@@ -97,7 +186,77 @@ mod tests {
         let sut = ResponseBuilder::new(200).build_with_cors(req.method(), request_origin, &cfg);
 
         let vary_header = sut.header(http::header::VARY.as_str());
-        assert_eq!(vary_header.is_none(), true);
+        assert!(vary_header.is_none());
+        assert_eq!(
+            sut.header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
+                .expect("Header Access-Control-Allow-Origin not present")
+                .as_str()
+                .expect("Could not convert value to str"),
+            ALL_ORIGINS
+        );
+    }
+
+    #[test]
+    fn builder_with_cors_should_reflect_origin_for_credentialed_wildcard() {
+        let cfg = CorsConfig::new(
+            ALL_ORIGINS.to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            None,
+        );
+        let sut = ResponseBuilder::new(200).build_with_cors(
+            &Method::Get,
+            "http://bar.baz".to_string(),
+            &cfg,
+        );
+
+        let test_data = vec![
+            (
+                http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str(),
+                "http://bar.baz",
+            ),
+            (
+                http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS.as_str(),
+                "true",
+            ),
+            (http::header::VARY.as_str(), "Origin"),
+        ];
+        for (header, expected) in test_data {
+            let actual = sut
+                .header(header)
+                .expect("Header not present")
+                .as_str()
+                .expect("Could not convert value to str");
+            assert_eq!(actual, expected, "{}", header);
+        }
+    }
+
+    #[test]
+    fn vary_header_should_not_be_set_if_disabled() {
+        let cfg = CorsConfig::new(
+            "http://localhost:4000,http://localhost:4005".to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            None,
+        )
+        .with_vary_emitted(false);
+
+        let sut = ResponseBuilder::new(200).build_with_cors(
+            &Method::Get,
+            "http://localhost:4005".to_string(),
+            &cfg,
+        );
+
+        assert!(sut.header(http::header::VARY.as_str()).is_none());
+        assert_eq!(
+            sut.header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
+                .expect("Header Access-Control-Allow-Origin not present")
+                .as_str()
+                .expect("Could not convert value to str"),
+            "http://localhost:4005"
+        );
     }
 
     #[test]
@@ -110,13 +269,13 @@ mod tests {
             .body(())
             .build();
 
-        let cfg = CorsConfig {
-            allowed_origins: allowed_origins.to_string(),
-            allowed_methods: ALL_METHODS.to_string(),
-            allowed_headers: ALL_HEADERS.to_string(),
-            allow_credentials: true,
-            max_age: None,
-        };
+        let cfg = CorsConfig::new(
+            allowed_origins.to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            None,
+        );
         let request_origin = req
             .header(http::header::ORIGIN.as_str())
             .unwrap_or(&HeaderValue::string(String::default()))
@@ -143,13 +302,13 @@ mod tests {
             .body(())
             .build();
 
-        let cfg = CorsConfig {
-            allowed_origins: allowed_origins.to_string(),
-            allowed_methods: ALL_METHODS.to_string(),
-            allowed_headers: ALL_HEADERS.to_string(),
-            allow_credentials: true,
-            max_age: None,
-        };
+        let cfg = CorsConfig::new(
+            allowed_origins.to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            None,
+        );
         let request_origin = req
             .header(http::header::ORIGIN.as_str())
             .unwrap_or(&HeaderValue::string(String::default()))
@@ -157,15 +316,40 @@ mod tests {
             .unwrap()
             .to_string();
         let sut = ResponseBuilder::new(200).build_with_cors(req.method(), request_origin, &cfg);
-        assert_eq!(
-            sut.header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
-                .is_none(),
-            true
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
+            .is_none());
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS.as_str())
+            .is_none());
+    }
+
+    #[test]
+    fn builder_with_cors_should_return_clean_forbidden_response_for_invalid_origin() {
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            Some(300),
         );
+        let sut = ResponseBuilder::new(200)
+            .header(http::header::CONTENT_TYPE.as_str(), "text/plain")
+            .body("secret")
+            .build_with_cors(&Method::Get, "http://localhost:8080".to_string(), &cfg);
+
+        assert_eq!(sut.status(), &http::StatusCode::FORBIDDEN.as_u16());
+        assert!(sut.body().is_empty());
+        assert!(sut.header(http::header::CONTENT_TYPE.as_str()).is_none());
+        assert!(!sut
+            .headers()
+            .any(|(name, _)| name.to_lowercase().starts_with("access-control-")));
         assert_eq!(
-            sut.header(http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS.as_str())
-                .is_none(),
-            true
+            sut.header(http::header::VARY.as_str())
+                .expect("Header Vary not present")
+                .as_str()
+                .expect("Could not convert value to str"),
+            "Origin"
         );
     }
 
@@ -196,16 +380,12 @@ mod tests {
                 .body(())
                 .build_with_cors(req.method(), request_origin, &cfg);
 
-        assert_eq!(
-            sut.header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
-                .is_some(),
-            true
-        );
-        assert_eq!(
-            sut.header(http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS.as_str())
-                .is_some(),
-            true
-        );
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
+            .is_some());
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS.as_str())
+            .is_some());
     }
 
     #[test]
@@ -227,15 +407,120 @@ mod tests {
             .to_string();
         let sut = ResponseBuilder::new(200).build_with_cors(req.method(), request_origin, &cfg);
 
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
+            .is_none());
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS.as_str())
+            .is_none());
+    }
+
+    #[test]
+    fn login_response_should_set_cookie_and_cors_headers() {
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            "POST".to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            None,
+        );
+        let cookie = Cookie::new("session", "abc", true, true, SameSite::Strict);
+        let sut = login_response(
+            &Method::Post,
+            "http://localhost:4200".to_string(),
+            &cfg,
+            cookie,
+        );
+
+        assert_eq!(sut.status(), &http::StatusCode::OK.as_u16());
+        assert!(sut.header(http::header::SET_COOKIE.as_str()).is_some());
         assert_eq!(
             sut.header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
-                .is_none(),
-            true
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            "http://localhost:4200"
+        );
+    }
+
+    #[test]
+    fn login_response_should_be_forbidden_for_invalid_origins() {
+        let test_data = vec![
+            ("http://localhost:4200", "http://localhost:8080"),
+            (ALL_ORIGINS, "http://localhost:8080"),
+        ];
+        for (allowed_origins, request_origin) in test_data {
+            let cfg = CorsConfig::new(
+                allowed_origins.to_string(),
+                "POST".to_string(),
+                ALL_HEADERS.to_string(),
+                true,
+                None,
+            );
+            let cookie = Cookie::new("session", "abc", true, true, SameSite::Strict);
+            let sut = login_response(&Method::Post, request_origin.to_string(), &cfg, cookie);
+
+            assert_eq!(sut.status(), &http::StatusCode::FORBIDDEN.as_u16());
+            assert!(sut.header(http::header::SET_COOKIE.as_str()).is_none());
+        }
+    }
+
+    #[test]
+    fn build_with_cors_opt_should_not_set_cors_headers_without_origin() {
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            None,
+        );
+        let sut = ResponseBuilder::new(200).build_with_cors_opt(&Method::Get, None, &cfg);
+        let expected = ResponseBuilder::new(200).build_with_cors(&Method::Get, String::new(), &cfg);
+
+        assert_eq!(sut.status(), &200);
+        assert_eq!(sut.headers().count(), 0);
+        assert_eq!(sut.headers().count(), expected.headers().count());
+
+        let sut = ResponseBuilder::new(200).build_with_cors_opt(
+            &Method::Get,
+            Some("http://localhost:4200"),
+            &cfg,
         );
         assert_eq!(
-            sut.header(http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS.as_str())
-                .is_none(),
-            true
+            sut.header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
+                .expect("Header ACCESS_CONTROL_ALLOW_ORIGIN not present")
+                .as_str()
+                .expect("Could not convert value to str"),
+            "http://localhost:4200"
         );
     }
+
+    #[test]
+    fn build_with_cors_should_never_add_preflight_headers() {
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            Some(300),
+        );
+        let sut = ResponseBuilder::new(204).build_with_cors(
+            &Method::Options,
+            "http://localhost:4200".to_string(),
+            &cfg,
+        );
+
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
+            .is_some());
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_METHODS.as_str())
+            .is_none());
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_HEADERS.as_str())
+            .is_none());
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_MAX_AGE.as_str())
+            .is_none());
+    }
 }
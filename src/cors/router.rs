@@ -1,65 +1,205 @@
-use spin_sdk::http::{HeaderValue, Params, Request, Response, ResponseBuilder, Router};
+use spin_sdk::http::{Params, Request, Response, ResponseBuilder, Router};
 
-use super::{build_cors_headers, is_method_allowed, CorsConfig, ALL_ORIGINS, NO_ORIGINS};
+use crate::request::{match_path_template, Contrib};
+
+use super::{
+    are_headers_allowed, build_cors_headers, is_method_allowed, CorsConfig, CorsResponseBuilder,
+};
 
 /// Trait to add CORS capabilities to spin_sdk::http::Router
 pub trait CorsRouter {
     /// Register handler for CORS OPTIONS requests
     fn register_options_handler(&mut self, cors_config: &CorsConfig);
+
+    /// Register handler for CORS OPTIONS requests, which only answers preflights for the provided routes
+    ///
+    /// Each route consists of a path template (`:name` matches a single segment, a trailing `*` matches the remainder)
+    /// and the comma-separated methods the route serves. Preflights for unknown paths result in `404 Not Found`,
+    /// preflights requesting a method the matching routes don't serve result in `405 Method Not Allowed`
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::Router;
+    /// use spin_contrib_http::cors::{CorsConfig, CorsRouter, ALL_HEADERS, ALL_METHODS};
+    ///
+    /// let cfg = CorsConfig::new(
+    ///   "http://localhost:4200".to_string(),
+    ///   ALL_METHODS.to_string(),
+    ///   ALL_HEADERS.to_string(),
+    ///   false,
+    ///   None,
+    /// );
+    /// let mut router = Router::default();
+    /// router.register_options_handler_for_routes(&cfg, &[("/users", "GET,POST"), ("/users/:id", "GET,PUT")]);
+    /// ```
+    fn register_options_handler_for_routes(
+        &mut self,
+        cors_config: &CorsConfig,
+        routes: &[(&str, &str)],
+    );
+
+    /// Dispatches the request and applies CORS to the response.
+    ///
+    /// Preflights are answered directly (see `preflight_response`) before the request is dispatched, so `OPTIONS`
+    /// routes of the router (including handlers added by `register_options_handler`) never receive preflights.
+    /// Other `OPTIONS` requests are answered the same way, unless `CorsConfig::with_options_passthrough`
+    /// is enabled, which dispatches them to the routes of the router
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::{Params, Request, Response, Router};
+    /// use spin_contrib_http::cors::{CorsConfig, CorsRouter, ALL_HEADERS, ALL_METHODS, ALL_ORIGINS};
+    ///
+    /// fn handle(req: Request) -> Response {
+    ///   let cfg = CorsConfig::new(
+    ///     ALL_ORIGINS.to_string(),
+    ///     ALL_METHODS.to_string(),
+    ///     ALL_HEADERS.to_string(),
+    ///     false,
+    ///     Some(3600),
+    ///   );
+    ///   let mut router = Router::default();
+    ///   router.get("/", |_: Request, _: Params| Response::new(200, ()));
+    ///   router.handle_with_cors(req, &cfg)
+    /// }
+    /// ```
+    fn handle_with_cors(&mut self, req: Request, cors_config: &CorsConfig) -> Response;
 }
 
 impl CorsRouter for Router {
     fn register_options_handler(&mut self, cors_config: &CorsConfig) {
         let cfg = cors_config.clone();
-        self.options(
-            "/*",
-            move |req: Request, _: Params| -> anyhow::Result<Response> {
-                options_handler(&req, &cfg)
-            },
-        )
+        self.options("/*", move |req: Request, _: Params| -> Response {
+            preflight_response(&req, &cfg)
+        })
     }
-}
 
-fn options_handler(req: &Request, cors_config: &CorsConfig) -> anyhow::Result<Response> {
-    let req_origin = req
-        .header(http::header::ORIGIN.as_str())
-        .unwrap_or(&HeaderValue::string(String::default()))
-        .as_str()
-        .unwrap()
-        .to_string();
-
-    if (cors_config.allowed_origins != ALL_ORIGINS
-        && (cors_config.allowed_origins == NO_ORIGINS
-            || !cors_config.allowed_origins.contains(&req_origin)))
-        || req_origin.is_empty()
-    {
-        return Ok(Response::new(403, ()));
+    fn register_options_handler_for_routes(
+        &mut self,
+        cors_config: &CorsConfig,
+        routes: &[(&str, &str)],
+    ) {
+        let cfg = cors_config.clone();
+        let routes = routes
+            .iter()
+            .map(|(template, methods)| (template.to_string(), methods.to_string()))
+            .collect::<Vec<(String, String)>>();
+        self.options("/*", move |req: Request, _: Params| -> Response {
+            let matching = routes
+                .iter()
+                .filter(|(template, _)| match_path_template(req.path(), template).is_some())
+                .collect::<Vec<_>>();
+            if matching.is_empty() {
+                return Response::new(http::StatusCode::NOT_FOUND, ());
+            }
+            let requested_method = req
+                .get_header_value_as_string(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str());
+            if !matching
+                .iter()
+                .any(|(_, methods)| is_method_allowed(methods, &requested_method))
+            {
+                return Response::new(http::StatusCode::METHOD_NOT_ALLOWED, ());
+            }
+            preflight_response(&req, &cfg)
+        })
     }
 
-    let requested_method = req
-        .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str())
-        .unwrap_or(&HeaderValue::string(String::default()))
-        .as_str()
-        .unwrap()
-        .to_string();
+    fn handle_with_cors(&mut self, req: Request, cors_config: &CorsConfig) -> Response {
+        if req.is_options() {
+            let is_preflight = req
+                .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str())
+                .is_some();
+            if is_preflight || !cors_config.options_passthrough {
+                return preflight_response(&req, cors_config);
+            }
+        }
+        let method = req.method().clone();
+        let request_origin = req.get_header_value_as_string(http::header::ORIGIN.as_str());
+        self.handle(req)
+            .into_builder()
+            .build_with_cors(&method, request_origin, cors_config)
+    }
+}
+
+/// Builds the response to a CORS preflight request, which is useful if requests are dispatched without `Router`
+///
+/// Valid preflights are answered with `204 No Content` and the corresponding `Access-Control-*` headers.
+/// Preflights are rejected with `403 Forbidden` if the origin is missing or not allowed, with
+/// `405 Method Not Allowed` if the requested method is missing or not allowed, and with
+/// `CorsConfig::with_disallowed_headers_status` (`403 Forbidden` by default) if requested headers are not allowed
+///
+/// # Example
+/// ```rust
+/// use spin_sdk::http::{Request, Response};
+/// use spin_contrib_http::cors::{preflight_response, CorsConfig};
+/// use spin_contrib_http::request::Contrib;
+///
+/// fn handle(req: Request) -> Response {
+///   let cfg = CorsConfig::permissive();
+///   if req.is_preflight_request() {
+///     return preflight_response(&req, &cfg);
+///   }
+///   Response::new(200, ())
+/// }
+/// ```
+pub fn preflight_response(req: &Request, cors_config: &CorsConfig) -> Response {
+    let req_origin = req.get_header_value_as_string(http::header::ORIGIN.as_str());
+    let requested_method =
+        req.get_header_value_as_string(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str());
+
+    if req_origin.is_empty() || !cors_config.allows_origin(&req_origin) {
+        trace_preflight(&req_origin, &requested_method, "denied: origin not allowed");
+        return Response::new(403, ());
+    }
 
     if requested_method.is_empty()
         || !is_method_allowed(&cors_config.allowed_methods, &requested_method)
     {
-        return Ok(Response::new(405, ()));
+        trace_preflight(&req_origin, &requested_method, "denied: method not allowed");
+        return Response::new(405, ());
+    }
+
+    let requested_headers =
+        req.get_header_value_as_string(http::header::ACCESS_CONTROL_REQUEST_HEADERS.as_str());
+
+    if !are_headers_allowed(&cors_config.allowed_headers, &requested_headers) {
+        trace_preflight(
+            &req_origin,
+            &requested_method,
+            "denied: headers not allowed",
+        );
+        return Response::new(cors_config.disallowed_headers_status, ());
     }
-    let headers = build_cors_headers(req.method(), req_origin, cors_config);
-    Ok(ResponseBuilder::new(http::StatusCode::NO_CONTENT)
+    trace_preflight(&req_origin, &requested_method, "allowed");
+    let headers = build_cors_headers(
+        req.method(),
+        req_origin,
+        &requested_method,
+        &requested_headers,
+        cors_config,
+    );
+    ResponseBuilder::new(http::StatusCode::NO_CONTENT)
         .headers(headers)
         .body(())
-        .build())
+        .build()
 }
 
+/// Logs the decision about a preflight request at debug level (requires the `tracing` feature)
+#[cfg(feature = "tracing")]
+fn trace_preflight(origin: &str, method: &str, decision: &str) {
+    tracing::debug!(origin, method, decision, "CORS preflight");
+}
+
+#[cfg(not(feature = "tracing"))]
+fn trace_preflight(_origin: &str, _method: &str, _decision: &str) {}
+
 #[cfg(test)]
 mod tests {
-    use spin_sdk::http::{Method, RequestBuilder};
+    use spin_sdk::http::{Method, Params, Request, RequestBuilder, Response, Router};
 
-    use crate::cors::{router::options_handler, CorsConfig, ALL_HEADERS};
+    use crate::cors::{
+        preflight_response, CorsConfig, CorsRouter, ALL_HEADERS, ALL_METHODS, ALL_ORIGINS,
+    };
 
     #[test]
     fn preflights_with_invalid_origin_should_result_in_forbidden() -> anyhow::Result<()> {
@@ -74,7 +214,7 @@ mod tests {
             true,
             Some(300),
         );
-        let sut = options_handler(&req, &cfg)?;
+        let sut = preflight_response(&req, &cfg);
         assert_eq!(sut.status(), &http::StatusCode::FORBIDDEN.as_u16());
         Ok(())
     }
@@ -98,9 +238,384 @@ mod tests {
             Some(300),
         );
 
-        let sut = options_handler(&req, &cfg)?;
+        let sut = preflight_response(&req, &cfg);
 
         assert_eq!(sut.status(), &http::StatusCode::METHOD_NOT_ALLOWED.as_u16());
         Ok(())
     }
+
+    #[test]
+    fn preflight_should_echo_only_requested_method_if_reflection_is_enabled() -> anyhow::Result<()>
+    {
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), "POST")
+            .build();
+
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            "POST,PUT".to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            Some(300),
+        );
+        let sut = preflight_response(&req, &cfg);
+        assert_eq!(
+            sut.header(http::header::ACCESS_CONTROL_ALLOW_METHODS.as_str())
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            "POST,PUT"
+        );
+
+        let sut = preflight_response(&req, &cfg.with_reflected_request_method(true));
+        assert_eq!(sut.status(), &http::StatusCode::NO_CONTENT.as_u16());
+        assert_eq!(
+            sut.header(http::header::ACCESS_CONTROL_ALLOW_METHODS.as_str())
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            "POST"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn preflight_must_reject_disallowed_requested_headers() -> anyhow::Result<()> {
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), "POST")
+            .header(
+                http::header::ACCESS_CONTROL_REQUEST_HEADERS.as_str(),
+                "content-type, x-custom",
+            )
+            .build();
+
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            "POST".to_string(),
+            "Content-Type".to_string(),
+            true,
+            Some(300),
+        );
+
+        let sut = preflight_response(&req, &cfg);
+        assert_eq!(sut.status(), &http::StatusCode::FORBIDDEN.as_u16());
+
+        let cfg = cfg.with_disallowed_headers_status(http::StatusCode::BAD_REQUEST);
+        let sut = preflight_response(&req, &cfg);
+        assert_eq!(sut.status(), &http::StatusCode::BAD_REQUEST.as_u16());
+        Ok(())
+    }
+
+    #[test]
+    fn preflight_response_should_answer_valid_and_reject_invalid_preflights() {
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            "GET,POST".to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            None,
+        );
+        let test_data = vec![
+            (
+                "http://localhost:4200",
+                "POST",
+                http::StatusCode::NO_CONTENT,
+            ),
+            ("http://localhost:8080", "POST", http::StatusCode::FORBIDDEN),
+            ("", "POST", http::StatusCode::FORBIDDEN),
+            (
+                "http://localhost:4200",
+                "DELETE",
+                http::StatusCode::METHOD_NOT_ALLOWED,
+            ),
+            (
+                "http://localhost:4200",
+                "",
+                http::StatusCode::METHOD_NOT_ALLOWED,
+            ),
+        ];
+        for (origin, requested_method, expected) in test_data {
+            let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+                .header(http::header::ORIGIN.as_str(), origin)
+                .header(
+                    http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(),
+                    requested_method,
+                )
+                .build();
+            let sut = preflight_response(&req, &cfg);
+            assert_eq!(
+                sut.status(),
+                &expected.as_u16(),
+                "{} {}",
+                origin,
+                requested_method
+            );
+            assert_eq!(
+                sut.header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
+                    .is_some(),
+                expected == http::StatusCode::NO_CONTENT
+            );
+        }
+    }
+
+    #[test]
+    fn preflight_should_reject_malformed_origins() -> anyhow::Result<()> {
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+            .header(
+                http::header::ORIGIN.as_str(),
+                "http://localhost:4200\r\nSet-Cookie: a=b",
+            )
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), "POST")
+            .build();
+
+        let cfg = CorsConfig::new(
+            ALL_ORIGINS.to_string(),
+            ALL_METHODS.to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            None,
+        );
+        let sut = preflight_response(&req, &cfg);
+        assert_eq!(sut.status(), &http::StatusCode::FORBIDDEN.as_u16());
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn preflight_should_match_allowed_headers_case_insensitively() -> anyhow::Result<()> {
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), "POST")
+            .header(
+                http::header::ACCESS_CONTROL_REQUEST_HEADERS.as_str(),
+                "content-type",
+            )
+            .build();
+
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            "POST".to_string(),
+            "Content-Type".to_string(),
+            false,
+            None,
+        );
+        let sut = preflight_response(&req, &cfg);
+        assert_eq!(sut.status(), &http::StatusCode::NO_CONTENT.as_u16());
+        Ok(())
+    }
+
+    #[test]
+    fn preflight_should_reflect_requested_headers_with_credentials_if_configured(
+    ) -> anyhow::Result<()> {
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), "POST")
+            .header(
+                http::header::ACCESS_CONTROL_REQUEST_HEADERS.as_str(),
+                "content-type, x-custom",
+            )
+            .build();
+
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            "POST".to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            Some(300),
+        );
+        let sut = preflight_response(&req, &cfg);
+        assert_eq!(
+            sut.header(http::header::ACCESS_CONTROL_ALLOW_HEADERS.as_str())
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            ALL_HEADERS
+        );
+
+        let sut = preflight_response(&req, &cfg.allow_any_header_but_reflect());
+        assert_eq!(sut.status(), &http::StatusCode::NO_CONTENT.as_u16());
+        assert_eq!(
+            sut.header(http::header::ACCESS_CONTROL_ALLOW_HEADERS.as_str())
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            "content-type, x-custom"
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn preflight_decisions_should_be_unchanged_with_tracing_enabled() -> anyhow::Result<()> {
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            "POST".to_string(),
+            ALL_HEADERS.to_string(),
+            true,
+            Some(300),
+        );
+        let test_data = vec![
+            (
+                "http://localhost:4200",
+                "POST",
+                http::StatusCode::NO_CONTENT,
+            ),
+            (
+                "http://localhost:4200",
+                "PUT",
+                http::StatusCode::METHOD_NOT_ALLOWED,
+            ),
+            ("http://bar.com", "POST", http::StatusCode::FORBIDDEN),
+        ];
+        for (origin, method, expected) in test_data {
+            let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+                .header(http::header::ORIGIN.as_str(), origin)
+                .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), method)
+                .build();
+            let sut = preflight_response(&req, &cfg);
+            assert_eq!(sut.status(), &expected.as_u16());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn handle_with_cors_should_apply_cors_to_routes_and_preflights() {
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            "GET,POST".to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            Some(300),
+        );
+        let mut router = Router::default();
+        router.get("/", |_: Request, _: Params| Response::new(200, "hello"));
+
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar/")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .build();
+        let sut = router.handle_with_cors(req, &cfg);
+        assert_eq!(sut.status(), &http::StatusCode::OK.as_u16());
+        assert_eq!(sut.body(), b"hello");
+        assert_eq!(
+            sut.header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN.as_str())
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            "http://localhost:4200"
+        );
+
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar/")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), "POST")
+            .build();
+        let sut = router.handle_with_cors(req, &cfg);
+        assert_eq!(sut.status(), &http::StatusCode::NO_CONTENT.as_u16());
+        assert_eq!(
+            sut.header(http::header::ACCESS_CONTROL_ALLOW_METHODS.as_str())
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            "GET,POST"
+        );
+    }
+
+    #[test]
+    fn handle_with_cors_should_answer_preflights_despite_user_options_routes() {
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            "GET,POST".to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            Some(300),
+        );
+        let mut router = Router::default();
+        router.options("/foo", |_: Request, _: Params| Response::new(200, "foo"));
+        router.post("/bar", |_: Request, _: Params| Response::new(201, ()));
+
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar/bar")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), "POST")
+            .build();
+        let sut = router.handle_with_cors(req, &cfg);
+        assert_eq!(sut.status(), &http::StatusCode::NO_CONTENT.as_u16());
+        let actual = sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_METHODS.as_str())
+            .expect("Header ACCESS_CONTROL_ALLOW_METHODS not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(actual, "GET,POST");
+    }
+
+    #[test]
+    fn handle_with_cors_should_pass_non_preflight_options_to_routes_if_configured() {
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            "GET,POST".to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            Some(300),
+        )
+        .with_options_passthrough(true);
+        let mut router = Router::default();
+        router.options("/resource", |_: Request, _: Params| {
+            Response::builder()
+                .status(200)
+                .header("allow", "GET, POST, OPTIONS")
+                .build()
+        });
+
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar/resource").build();
+        let sut = router.handle_with_cors(req, &cfg);
+        assert_eq!(sut.status(), &http::StatusCode::OK.as_u16());
+        assert_eq!(
+            sut.header("allow").unwrap().as_str().unwrap(),
+            "GET, POST, OPTIONS"
+        );
+
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar/resource")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), "POST")
+            .build();
+        let sut = router.handle_with_cors(req, &cfg);
+        assert_eq!(sut.status(), &http::StatusCode::NO_CONTENT.as_u16());
+        assert!(sut
+            .header(http::header::ACCESS_CONTROL_ALLOW_METHODS.as_str())
+            .is_some());
+    }
+
+    #[test]
+    fn options_handler_for_routes_should_only_answer_preflights_for_registered_routes() {
+        let cfg = CorsConfig::new(
+            "http://localhost:4200".to_string(),
+            "GET,POST,PUT,DELETE".to_string(),
+            ALL_HEADERS.to_string(),
+            false,
+            Some(300),
+        );
+        let mut router = Router::default();
+        router.register_options_handler_for_routes(
+            &cfg,
+            &[("/users", "GET,POST"), ("/users/:id", "GET,PUT")],
+        );
+
+        let test_data = vec![
+            ("/users/42", "PUT", http::StatusCode::NO_CONTENT),
+            ("/users", "POST", http::StatusCode::NO_CONTENT),
+            ("/users/42", "DELETE", http::StatusCode::METHOD_NOT_ALLOWED),
+            ("/orders", "GET", http::StatusCode::NOT_FOUND),
+            ("/users/42/posts", "GET", http::StatusCode::NOT_FOUND),
+        ];
+        for (path, method, expected) in test_data {
+            let req = RequestBuilder::new(Method::Options, format!("http://foo.bar{}", path))
+                .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+                .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), method)
+                .build();
+            let sut = router.handle(req);
+            assert_eq!(sut.status(), &expected.as_u16(), "{} {}", method, path);
+        }
+    }
 }
@@ -0,0 +1,114 @@
+use std::fmt::Display;
+
+use spin_sdk::http::{IntoResponse, Response};
+
+/// Errors produced by the helpers of this crate
+///
+/// `ContribError` implements `std::error::Error`, so it can be used without depending on `anyhow`.
+/// It also implements `IntoResponse`, which allows returning it directly from Spin handlers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContribError {
+    /// A value could not be serialized or deserialized
+    Serialization(String),
+    /// A header name or value produced by the application is invalid
+    InvalidHeader(String),
+    /// The origin of a request is invalid or not allowed
+    InvalidOrigin(String),
+    /// A configuration value is invalid
+    InvalidConfiguration(String),
+    /// The request body exceeds the provided limit (in bytes)
+    PayloadTooLarge(usize),
+    /// Reading a streamed request body or writing to a streaming response body failed
+    /// (e.g. because the client disconnected)
+    Stream(String),
+}
+
+impl ContribError {
+    /// Returns the HTTP status code representing the error
+    pub fn status_code(&self) -> http::StatusCode {
+        match self {
+            ContribError::Serialization(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            ContribError::InvalidHeader(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            ContribError::InvalidOrigin(_) => http::StatusCode::FORBIDDEN,
+            ContribError::InvalidConfiguration(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            ContribError::PayloadTooLarge(_) => http::StatusCode::PAYLOAD_TOO_LARGE,
+            ContribError::Stream(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl Display for ContribError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContribError::Serialization(msg) => write!(f, "serialization failed: {}", msg),
+            ContribError::InvalidHeader(msg) => write!(f, "invalid header: {}", msg),
+            ContribError::InvalidOrigin(msg) => write!(f, "invalid origin: {}", msg),
+            ContribError::InvalidConfiguration(msg) => write!(f, "invalid configuration: {}", msg),
+            ContribError::PayloadTooLarge(limit) => {
+                write!(f, "payload exceeds the limit of {} bytes", limit)
+            }
+            ContribError::Stream(msg) => write!(f, "streaming body failed: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ContribError {}
+
+impl From<ContribError> for Response {
+    fn from(value: ContribError) -> Self {
+        Response::new(value.status_code(), value.to_string())
+    }
+}
+
+impl IntoResponse for ContribError {
+    fn into_response(self) -> Response {
+        self.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contrib_error_should_convert_into_response() {
+        let test_data = vec![
+            (
+                ContribError::Serialization("boom".to_string()),
+                http::StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+            (
+                ContribError::InvalidHeader("location".to_string()),
+                http::StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+            (
+                ContribError::InvalidOrigin("http://evil.com".to_string()),
+                http::StatusCode::FORBIDDEN,
+            ),
+            (
+                ContribError::InvalidConfiguration("unknown method FOO".to_string()),
+                http::StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+            (
+                ContribError::PayloadTooLarge(1024),
+                http::StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+            (
+                ContribError::Stream("closed".to_string()),
+                http::StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+        ];
+        for (err, expected) in test_data {
+            let message = err.to_string();
+            let sut = err.into_response();
+            assert_eq!(sut.status(), &expected.as_u16());
+            assert_eq!(sut.body(), message.as_bytes());
+        }
+    }
+
+    #[test]
+    fn contrib_error_should_be_usable_with_anyhow() {
+        let sut: anyhow::Error = ContribError::InvalidHeader("location".to_string()).into();
+        assert_eq!(sut.to_string(), "invalid header: location");
+    }
+}
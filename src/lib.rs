@@ -6,16 +6,28 @@
 //! - Cross-Origin Resource-Sharing (CORS)
 //! - Response helpers to produce common HTTP responses
 //! - Request helpers to examine incoming HTTP requests
+//! - Parsing media types for content negotiation
+//! - Formatting Server-Sent Events (SSE)
 
 #![warn(rust_2018_idioms)]
 #![warn(rust_2021_compatibility)]
 #![warn(missing_docs)]
 
+mod httpdate;
+
 /// Helpers to simplify working with cookies
 pub mod cookies;
 /// Helpers to simplify working with Cross-Origin Resource Sharing (CORS)
 pub mod cors;
+/// Error type used by the helpers of this crate
+pub mod error;
+/// Helpers for parsing media types
+pub mod mediatype;
 /// Extensions for working with HTTP requests
 pub mod request;
 /// Extensions for working with HTTP responses
 pub mod response;
+/// Extensions for working with the Spin router
+pub mod router;
+/// Helpers for sending Server-Sent Events (SSE)
+pub mod sse;
@@ -0,0 +1,148 @@
+use std::fmt::Display;
+
+/// Representation of a media type as used in `Accept` and `Content-Type` headers
+///
+/// Type, subtype and parameter names are normalized to lowercase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    /// The top-level type (e.g. `application`), may be `*`
+    pub type_: String,
+    /// The subtype (e.g. `vnd.api+json`), may be `*`
+    pub subtype: String,
+    /// The parameters (e.g. `charset=utf-8`) in the order they were provided
+    pub parameters: Vec<(String, String)>,
+}
+
+impl MediaType {
+    /// Parses a media type like `application/vnd.api+json; charset=utf-8`.
+    ///
+    /// Returns `None` if the value is not a valid media type
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_contrib_http::mediatype::MediaType;
+    ///
+    /// let media_type = MediaType::parse("application/vnd.api+json; charset=utf-8").unwrap();
+    /// assert_eq!(media_type.type_, "application");
+    /// assert_eq!(media_type.subtype, "vnd.api+json");
+    /// assert_eq!(media_type.suffix(), Some("json"));
+    /// assert_eq!(media_type.parameter("charset"), Some("utf-8"));
+    /// ```
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split(';');
+        let (type_, subtype) = parts.next()?.trim().split_once('/')?;
+        let type_ = type_.trim().to_lowercase();
+        let subtype = subtype.trim().to_lowercase();
+        if !is_token(&type_) || !is_token(&subtype) || (type_ == "*" && subtype != "*") {
+            return None;
+        }
+        let parameters = parts
+            .filter_map(|param| {
+                let (name, value) = param.split_once('=')?;
+                let name = name.trim().to_lowercase();
+                if !is_token(&name) {
+                    return None;
+                }
+                Some((name, value.trim().trim_matches('"').to_string()))
+            })
+            .collect();
+        Some(MediaType {
+            type_,
+            subtype,
+            parameters,
+        })
+    }
+
+    /// Returns the structured syntax suffix of the subtype (e.g. `json` for `vnd.api+json`)
+    pub fn suffix(&self) -> Option<&str> {
+        self.subtype.rsplit_once('+').map(|(_, suffix)| suffix)
+    }
+
+    /// Returns the value of the parameter with the given name (compared case-insensitively)
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the media type without parameters (e.g. `application/json`)
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.type_, self.subtype)
+    }
+
+    /// Determines if this media type matches the other one, honoring wildcards on both sides.
+    ///
+    /// Parameters are not taken into account
+    pub fn matches(&self, other: &MediaType) -> bool {
+        let type_matches = self.type_ == "*" || other.type_ == "*" || self.type_ == other.type_;
+        let subtype_matches =
+            self.subtype == "*" || other.subtype == "*" || self.subtype == other.subtype;
+        type_matches && subtype_matches
+    }
+
+    /// Determines if the media type represents JSON (`application/json` or a `+json` suffix)
+    pub fn is_json(&self) -> bool {
+        (self.type_ == "application" && self.subtype == "json") || self.suffix() == Some("json")
+    }
+}
+
+impl Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+        for (name, value) in &self.parameters {
+            write!(f, "; {}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+*".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_should_read_type_subtype_suffix_and_parameters() {
+        let sut = MediaType::parse("Application/vnd.api+JSON; charset=utf-8; profile=\"a b\"")
+            .expect("Could not parse media type");
+
+        assert_eq!(sut.type_, "application");
+        assert_eq!(sut.subtype, "vnd.api+json");
+        assert_eq!(sut.suffix(), Some("json"));
+        assert_eq!(sut.parameter("CHARSET"), Some("utf-8"));
+        assert_eq!(sut.parameter("profile"), Some("a b"));
+        assert!(sut.is_json());
+        assert_eq!(sut.essence(), "application/vnd.api+json");
+    }
+
+    #[test]
+    fn parse_should_reject_invalid_media_types() {
+        let test_data = vec!["", "json", "application/", "/json", "*/json", "a b/c"];
+        for data in test_data {
+            assert_eq!(MediaType::parse(data), None, "Media type: {}", data);
+        }
+    }
+
+    #[test]
+    fn matches_should_honor_wildcards() {
+        let json = MediaType::parse("application/json").unwrap();
+        let test_data = vec![
+            ("*/*", true),
+            ("application/*", true),
+            ("application/json", true),
+            ("text/*", false),
+            ("application/xml", false),
+        ];
+        for (other, expected) in test_data {
+            let other = MediaType::parse(other).unwrap();
+            assert_eq!(json.matches(&other), expected, "Media type: {}", other);
+        }
+    }
+}
@@ -1,6 +1,79 @@
-use spin_sdk::http::{HeaderValue, Method, Request};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "json")]
+use spin_sdk::http::Response;
+use spin_sdk::http::{Method, Request};
+
+#[cfg(feature = "anyhow")]
+use anyhow::{anyhow, bail, Result};
+use percent_encoding::percent_decode_str;
+
+use crate::cookies::decode_cookie_value;
+use crate::cors::{is_preflight, normalize_origin, NULL_ORIGIN};
+use crate::error::ContribError;
+use crate::httpdate::parse_http_date;
+use crate::mediatype::MediaType;
 
 const HEADER_SPIN_PATH_INFO: &str = "spin-path-info";
+const HEADER_SPIN_CLIENT_ADDR: &str = "spin-client-addr";
+const HEADER_X_FORWARDED_FOR: &str = "x-forwarded-for";
+const HEADER_X_REQUESTED_WITH: &str = "x-requested-with";
+const HEADER_FORWARDED: &str = "forwarded";
+const HEADER_X_FORWARDED_HOST: &str = "x-forwarded-host";
+const HEADER_X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+pub(crate) const HEADER_X_REQUEST_ID: &str = "x-request-id";
+
+/// Pagination parameters of a request (see `Contrib::pagination`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination {
+    /// The requested page (starting at 1)
+    pub page: u32,
+    /// The number of items per page
+    pub per_page: u32,
+}
+
+impl Pagination {
+    /// Returns the number of items to skip for the current page
+    pub fn offset(&self) -> u64 {
+        u64::from(self.page.saturating_sub(1)) * u64::from(self.per_page)
+    }
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Pagination {
+            page: 1,
+            per_page: 20,
+        }
+    }
+}
+
+/// A single element of the `Forwarded` header (RFC 7239)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardedElement {
+    /// The client that initiated the request (`for` parameter)
+    pub for_: Option<String>,
+    /// The protocol used to make the request (`proto` parameter)
+    pub proto: Option<String>,
+    /// The interface where the request came in to the proxy (`by` parameter)
+    pub by: Option<String>,
+    /// The `Host` header as received by the proxy (`host` parameter)
+    pub host: Option<String>,
+}
+
+/// The details of a CORS preflight request (see `Contrib::preflight_details`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreflightRequest {
+    /// The (normalized) origin of the request (`Origin` header)
+    pub origin: String,
+    /// The uppercased method of the actual request (`Access-Control-Request-Method` header)
+    pub requested_method: String,
+    /// The lowercased headers of the actual request (`Access-Control-Request-Headers` header)
+    pub requested_headers: Vec<String>,
+}
 
 /// Extensions for spin_sdk::http::Request
 pub trait Contrib {
@@ -19,7 +92,7 @@ pub trait Contrib {
     ///
     /// let segments = fake_req.get_route_segments();
     ///
-    /// assert_eq!(segments.is_some(), true);
+    /// assert!(segments.is_some());
     /// let segments = segments.unwrap();
     /// assert_eq!(segments.len(), 3);
     /// assert_eq!(segments[0], "foo");
@@ -28,88 +101,2512 @@ pub trait Contrib {
     /// ```
     fn get_route_segments(&self) -> Option<Vec<&str>>;
 
-    /// Determines if the request is a preflight request
+    /// returns owned route segments of the HTTP request.
+    ///
+    /// Same as `get_route_segments`, but the segments don't borrow from the request (segments are not decoded)
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("spin-path-info", "/foo/bar")
+    ///    .body(()).build();
+    ///
+    /// assert_eq!(fake_req.get_route_segments_owned(), Some(vec!["foo".to_string(), "bar".to_string()]));
+    /// ```
+    fn get_route_segments_owned(&self) -> Option<Vec<String>>;
+
+    /// returns the percent-decoded path of the HTTP request.
+    ///
+    /// `spin-path-info` (the path relative to the route of the component) is used, falling back to the path of
+    /// the URI if the header is not present. An empty path is returned as `/`. Unlike the inherent `Request::path`, the path is decoded
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/api/hello%20world?page=2")
+    ///    .header("spin-path-info", "/hello%20world")
+    ///    .body(()).build();
+    ///
+    /// assert_eq!(fake_req.get_path(), "/hello world");
+    /// assert_eq!(fake_req.get_path_and_query(), "/hello world?page=2");
+    /// ```
+    fn get_path(&self) -> String;
+
+    /// returns the percent-decoded path (see `get_path`) followed by the raw query string (if present)
+    fn get_path_and_query(&self) -> String;
+
+    /// returns the route segment at the provided (zero-based) index.
+    ///
+    /// If the index is out of range or the request was invoked using the root URL, `None` is returned
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("spin-path-info", "/foo/bar/baz")
+    ///    .body(()).build();
+    ///
+    /// assert_eq!(fake_req.get_route_segment(1), Some("bar"));
+    /// assert_eq!(fake_req.get_route_segment(3), None);
+    /// ```
+    fn get_route_segment(&self, index: usize) -> Option<&str>;
+
+    /// matches the route of the HTTP request against the provided template and extracts named parameters.
+    ///
+    /// Template segments starting with `:` capture the corresponding route segment. A trailing `*` segment
+    /// matches the remainder of the route, which is captured as `*`.
+    /// If the route does not match the template, `None` is returned
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("spin-path-info", "/users/42/posts/7")
+    ///    .body(()).build();
+    ///
+    /// let params = fake_req.match_route("/users/:id/posts/:post_id").unwrap();
+    /// assert_eq!(params["id"], "42");
+    /// assert_eq!(params["post_id"], "7");
+    /// assert!(fake_req.match_route("/orders/:id").is_none());
+    /// ```
+    fn match_route(&self, template: &str) -> Option<HashMap<String, String>>;
+
+    /// Determines if the request is a CORS preflight request
+    ///
+    /// A preflight request uses `OPTIONS` and carries both `Origin` and `Access-Control-Request-Method` headers.
+    /// This is the same definition used by the `cors` module
     fn is_preflight_request(&self) -> bool;
 
-    /// Returns a header value as String. If header is not present or value is empty, an empty string is returned
+    /// Returns the details of a CORS preflight request.
+    ///
+    /// If the request is not a preflight request (see `is_preflight_request`), `None` is returned
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Options, "http://foo/bar")
+    ///    .header("origin", "http://localhost:4200")
+    ///    .header("access-control-request-method", "put")
+    ///    .header("access-control-request-headers", "Content-Type, X-Custom")
+    ///    .body(()).build();
+    ///
+    /// let details = fake_req.preflight_details().unwrap();
+    /// assert_eq!(details.requested_method, "PUT");
+    /// assert_eq!(details.requested_headers, vec!["content-type", "x-custom"]);
+    /// ```
+    fn preflight_details(&self) -> Option<PreflightRequest>;
+
+    /// Returns a header value as String. If header is not present, value is empty or not valid UTF-8, an empty string is returned
     fn get_header_value_as_string(&self, header_name: &str) -> String;
-}
 
-impl Contrib for Request {
-    fn get_route_segments(&self) -> Option<Vec<&str>> {
-        let spin_path_header_value = self.header(HEADER_SPIN_PATH_INFO)?;
-        let header_value = spin_path_header_value.as_str()?;
-        if header_value.trim().is_empty() || header_value == "/" {
-            return None;
-        };
+    /// Returns a header value, looking up the header name case-insensitively.
+    ///
+    /// `Request::header` only finds headers stored under lowercase names, but `Request::set_header` keeps the
+    /// casing of the name. This lookup finds both. If header is not present or not valid UTF-8, `None` is returned
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let mut fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .body(()).build();
+    /// fake_req.set_header("X-Api-Key", "secret");
+    ///
+    /// assert_eq!(fake_req.get_header_ci("x-api-key"), Some("secret".to_string()));
+    /// ```
+    fn get_header_ci(&self, name: &str) -> Option<String>;
 
-        let mut segments = header_value.split('/').collect::<Vec<&str>>();
-        segments.remove(0);
-        Some(segments)
-    }
+    /// Determines if the comma-separated header contains the provided token (compared case-insensitively).
+    ///
+    /// Directive values are ignored, so `no-cache` is also found in `no-cache="set-cookie"`.
+    /// If header is not present, `false` is returned
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("connection", "keep-alive, Upgrade")
+    ///    .body(()).build();
+    ///
+    /// assert!(fake_req.header_contains_token("connection", "upgrade"));
+    /// assert!(!fake_req.header_contains_token("connection", "close"));
+    /// ```
+    fn header_contains_token(&self, name: &str, token: &str) -> bool;
 
-    fn is_preflight_request(&self) -> bool {
-        self.method() == &Method::Options && self.header(http::header::ORIGIN.as_str()).is_some()
-    }
+    /// Returns all elements of the `Forwarded` header (RFC 7239).
+    ///
+    /// If the header is not present, an empty vector is returned
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("forwarded", "for=192.0.2.60;proto=http;by=203.0.113.43")
+    ///    .body(()).build();
+    ///
+    /// let forwarded = fake_req.forwarded();
+    /// assert_eq!(forwarded.len(), 1);
+    /// assert_eq!(forwarded[0].for_.as_deref(), Some("192.0.2.60"));
+    /// assert_eq!(forwarded[0].proto.as_deref(), Some("http"));
+    /// ```
+    fn forwarded(&self) -> Vec<ForwardedElement>;
 
-    fn get_header_value_as_string(&self, header_name: &str) -> String {
-        self.header(header_name)
-            .unwrap_or(&HeaderValue::string(String::default()))
-            .as_str()
-            .unwrap()
-            .to_string()
-    }
-}
+    /// Returns the best match of the supported languages according to the `Accept-Language` header.
+    ///
+    /// Language tags are matched case-insensitively, and a supported language also matches
+    /// requested sub-tags (`en` matches `en-US`). `*` matches supported languages which aren't covered by a listed
+    /// language range, so languages excluded using `q=0` are never returned. If nothing matches, `None` is returned
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("accept-language", "de;q=0.7, en-US;q=0.9")
+    ///    .body(()).build();
+    ///
+    /// assert_eq!(fake_req.preferred_language(&["en", "de"]), Some("en".to_string()));
+    /// ```
+    fn preferred_language(&self, supported: &[&str]) -> Option<String>;
 
-#[cfg(test)]
-mod tests {
-    use spin_sdk::http::{Method, RequestBuilder};
+    /// Returns the best match of the supported charsets according to the `Accept-Charset` header.
+    ///
+    /// Charsets are matched case-insensitively. If the header is not present, any charset is acceptable and the
+    /// first supported charset is returned. `*` matches supported charsets which aren't listed explicitly, so
+    /// charsets excluded using `q=0` are never returned. If nothing matches, `None` is returned
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("accept-charset", "utf-8;q=0.8, iso-8859-1;q=0.9")
+    ///    .body(()).build();
+    ///
+    /// assert_eq!(fake_req.preferred_charset(&["utf-8", "iso-8859-1"]), Some("iso-8859-1".to_string()));
+    /// ```
+    fn preferred_charset(&self, supported: &[&str]) -> Option<String>;
 
-    use super::*;
+    /// Determines if the provided content coding (e.g. `gzip`) is acceptable according to `Accept-Encoding`.
+    ///
+    /// Codings are compared case-insensitively, and an explicit entry takes precedence over `*`.
+    /// Codings listed with `q=0` are never acceptable. If the header is not present, any coding is acceptable.
+    /// `identity` is acceptable unless excluded explicitly (`identity;q=0`, or `*;q=0` without an `identity` entry)
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("accept-encoding", "gzip;q=0, br")
+    ///    .body(()).build();
+    ///
+    /// assert!(!fake_req.accepts_encoding("gzip"));
+    /// assert!(fake_req.accepts_encoding("br"));
+    /// ```
+    fn accepts_encoding(&self, coding: &str) -> bool;
 
-    #[test]
-    fn get_route_segments_should_return_provided_segments() {
-        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
-            .header(HEADER_SPIN_PATH_INFO, "/foo/bar/baz")
-            .body(())
-            .build();
-        let segments = req.get_route_segments();
-        assert_eq!(segments.is_some(), true);
-        let segments = segments.unwrap();
-        assert_eq!(segments.len(), 3);
-        assert_eq!(segments[0], "foo");
-        assert_eq!(segments[1], "bar");
-        assert_eq!(segments[2], "baz");
-    }
+    /// Returns the address (IP and port) of the client which sent the request.
+    ///
+    /// The address provided by Spin (`spin-client-addr`) is used, falling back to the first `for` parameter of
+    /// `Forwarded` and the first value of `X-Forwarded-For`. IPv6 addresses may use bracket notation
+    /// (`[2001:db8::1]:443`). If no port is provided, port `0` is used. If no address can be parsed, `None` is returned
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("spin-client-addr", "[2001:db8::1]:443")
+    ///    .body(()).build();
+    ///
+    /// let addr = fake_req.remote_addr().unwrap();
+    /// assert_eq!(addr.ip().to_string(), "2001:db8::1");
+    /// assert_eq!(addr.port(), 443);
+    /// ```
+    fn remote_addr(&self) -> Option<SocketAddr>;
 
-    #[test]
-    fn get_route_segments_should_empty_vector_for_root_url() {
-        let test_data = vec!["/", "", " "];
-        for data in test_data {
-            let req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo.bar")
-                .header(HEADER_SPIN_PATH_INFO, data)
-                .body(())
-                .build();
+    /// Returns the host (including the port, if provided) the request was sent to.
+    ///
+    /// The `Host` header is used, falling back to `X-Forwarded-Host`. If neither is present, `None` is returned
+    fn host(&self) -> Option<String>;
 
-            assert_eq!(req.get_route_segments(), None);
-        }
-    }
+    /// Determines if the request was sent using HTTPS (based on `X-Forwarded-Proto`)
+    fn is_secure(&self) -> bool;
 
-    #[test]
-    fn get_header_value_as_string_should_return_correct_values() {
-        let test_data = vec![
-            ("my-header", "foo", "my-header", "foo"),
-            ("my-header", "", "my-header", ""),
-            ("my-header", "foo", "my-other-header", ""),
-            ("my-header", "", "my-other-header", ""),
-        ];
-        for data in test_data {
-            let req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo.bar")
-                .header(data.0, data.1)
-                .body(())
-                .build();
+    /// Returns the (lowercase) scheme the request was sent with.
+    ///
+    /// The first value of `X-Forwarded-Proto` is used. If the header is not present, `http` is returned
+    fn scheme(&self) -> String;
 
-            assert_eq!(req.get_header_value_as_string(data.2), data.3);
+    /// Determines if the provided ETag matches the `If-None-Match` header using weak comparison.
+    ///
+    /// Returns `false` if the header is not present. Use it to respond with `304 Not Modified`
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("if-none-match", "\"abc\", W/\"def\"")
+    ///    .body(()).build();
+    ///
+    /// assert!(fake_req.if_none_match_matches("\"def\""));
+    /// assert!(!fake_req.if_none_match_matches("\"xyz\""));
+    /// ```
+    fn if_none_match_matches(&self, etag: &str) -> bool;
+
+    /// Determines if the provided ETag matches the `If-Match` header using strong comparison.
+    ///
+    /// Weak ETags never match. Returns `false` if the header is not present
+    fn if_match_matches(&self, etag: &str) -> bool;
+
+    /// Determines if a `Range` request may be served partially, according to the `If-Range` header.
+    ///
+    /// Returns `true` if `If-Range` is not present, or if its validator still matches the current representation:
+    /// An ETag must match `current_etag` using strong comparison, an HTTP-date must exactly match `last_modified`.
+    /// If `false` is returned, the full representation must be sent instead of the requested range
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("range", "bytes=0-99")
+    ///    .header("if-range", "\"abc\"")
+    ///    .body(()).build();
+    ///
+    /// assert!(fake_req.if_range_satisfied("\"abc\"", None));
+    /// assert!(!fake_req.if_range_satisfied("\"def\"", None));
+    /// ```
+    fn if_range_satisfied(&self, current_etag: &str, last_modified: Option<SystemTime>) -> bool;
+
+    /// Returns the trimmed `User-Agent` header. If header is not present or value is empty, `None` is returned
+    fn user_agent(&self) -> Option<String>;
+
+    /// Returns the trimmed `Referer` header. If header is not present or value is empty, `None` is returned
+    fn referer(&self) -> Option<String>;
+
+    /// Returns the `Origin` header, normalized the same way CORS compares origins (trimmed and lowercased).
+    ///
+    /// If header is not present or value is empty, `None` is returned
+    fn origin(&self) -> Option<String>;
+
+    /// Determines if the request was sent using `XMLHttpRequest` (`X-Requested-With: XMLHttpRequest`, compared case-insensitively).
+    ///
+    /// If header is not present, `false` is returned
+    fn is_ajax(&self) -> bool;
+
+    /// Determines if the client prefers JSON over HTML according to the `Accept` header.
+    ///
+    /// Returns `true` only if a JSON media type (`application/json` or a `+json` suffix) is listed explicitly
+    /// with a quality greater than or equal to the quality of HTML (`text/html`, `application/xhtml+xml`).
+    /// Wildcards (such as `*/*` sent by browsers) are not taken into account
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let browser_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
+    ///    .body(()).build();
+    /// assert!(!browser_req.prefers_json());
+    ///
+    /// let api_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("accept", "application/json")
+    ///    .body(()).build();
+    /// assert!(api_req.prefers_json());
+    /// ```
+    fn prefers_json(&self) -> bool;
+
+    /// Determines if the request was sent from the provided host (e.g. to mitigate CSRF).
+    ///
+    /// The host of `Origin` (falling back to `Referer`) is compared case-insensitively with `expected_host`,
+    /// which may contain a scheme and a port. Default ports (`80` for `http`, `443` for `https`) are ignored.
+    /// If neither header is present (or `Origin` is `null`), `false` is returned
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Post, "http://foo/bar")
+    ///    .header("origin", "https://example.com:443")
+    ///    .body(()).build();
+    ///
+    /// assert!(fake_req.is_same_origin("example.com"));
+    /// assert!(!fake_req.is_same_origin("evil.com"));
+    /// ```
+    fn is_same_origin(&self, expected_host: &str) -> bool;
+
+    /// Returns the pagination parameters provided in the query string.
+    ///
+    /// `page` and `per_page` are read from the query string, `limit` and `offset` are accepted as alternatives.
+    /// Missing or non-numeric values fall back to `defaults`, and `per_page` is clamped to `1..=max_per_page`
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::{Contrib, Pagination};
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar?page=3&per_page=500")
+    ///    .body(()).build();
+    ///
+    /// let pagination = fake_req.pagination(Pagination::default(), 100);
+    /// assert_eq!(pagination.page, 3);
+    /// assert_eq!(pagination.per_page, 100);
+    /// assert_eq!(pagination.offset(), 200);
+    /// ```
+    fn pagination(&self, defaults: Pagination, max_per_page: u32) -> Pagination;
+
+    /// Returns the raw query string (everything after `?`) verbatim, without decoding.
+    ///
+    /// Useful for verifying signatures computed over the query string. If the request has no query, `None` is returned
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar?a=1&b=%20")
+    ///    .body(()).build();
+    ///
+    /// assert_eq!(fake_req.query_string(), Some("a=1&b=%20"));
+    /// ```
+    fn query_string(&self) -> Option<&str>;
+
+    /// Returns the correlation id of the request.
+    ///
+    /// The `X-Request-Id` header is used if present, otherwise a new UUID (v4) is generated.
+    /// Use `ContribResponseBuilder::with_request_id` to echo it on the response
+    ///
+    /// Requires the `uuid` feature
+    #[cfg(feature = "uuid")]
+    fn request_id(&self) -> String;
+
+    /// Returns all cookies sent with the request as (name, value) pairs.
+    ///
+    /// Duplicates and order are preserved, values are percent-decoded, and malformed pairs (without `=`) are skipped
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("cookie", "id=1; theme=dark%20blue")
+    ///    .body(()).build();
+    ///
+    /// let cookies = fake_req.parse_cookies();
+    /// assert_eq!(cookies[1], ("theme".to_string(), "dark blue".to_string()));
+    /// ```
+    fn parse_cookies(&self) -> Vec<(String, String)>;
+
+    /// Returns the (percent-decoded) value of the first cookie with the provided name.
+    ///
+    /// If the cookie is not present, `None` is returned
+    fn get_cookie(&self, name: &str) -> Option<String>;
+
+    /// Parses the (percent-decoded) value of the cookie with the provided name as boolean.
+    ///
+    /// `true`, `1` and `yes` are parsed as `true`, `false`, `0` and `no` as `false` (case-insensitive).
+    /// Returns `None` if the cookie is not present or has any other value
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("cookie", "beta=1; page_size=50")
+    ///    .body(()).build();
+    ///
+    /// assert_eq!(fake_req.get_cookie_as_bool("beta"), Some(true));
+    /// assert_eq!(fake_req.get_cookie_parsed::<u32>("page_size"), Some(50));
+    /// ```
+    fn get_cookie_as_bool(&self, name: &str) -> Option<bool>;
+
+    /// Parses the (percent-decoded) value of the cookie with the provided name using `FromStr`.
+    ///
+    /// Returns `None` if the cookie is not present or can't be parsed. Use `get_cookie_as` for JSON values
+    fn get_cookie_parsed<T: FromStr>(&self, name: &str) -> Option<T>;
+
+    /// Determines if a cookie with the provided name is present. Returns `false` if `Cookie` is not present
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("cookie", "session=abc; theme=dark")
+    ///    .body(()).build();
+    ///
+    /// assert!(fake_req.has_cookie("session"));
+    /// assert_eq!(fake_req.cookie_count(), 2);
+    /// ```
+    fn has_cookie(&self, name: &str) -> bool;
+
+    /// Returns the number of cookies sent with the request. Returns `0` if `Cookie` is not present
+    fn cookie_count(&self) -> usize;
+
+    /// Deserializes the value of the cookie with the provided name from JSON.
+    ///
+    /// The value may be plain JSON or base64-encoded JSON (standard or URL-safe alphabet).
+    /// Returns `None` if the cookie is not present and `Some(Err(..))` if it can't be deserialized
+    ///
+    /// Requires the `json` and `anyhow` features
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo/bar")
+    ///    .header("cookie", "prefs=%7B%22theme%22%3A%22dark%22%7D")
+    ///    .body(()).build();
+    ///
+    /// let prefs: serde_json::Value = fake_req.get_cookie_as("prefs").unwrap().unwrap();
+    /// assert_eq!(prefs["theme"], "dark");
+    /// ```
+    #[cfg(all(feature = "json", feature = "anyhow"))]
+    fn get_cookie_as<T: serde::de::DeserializeOwned>(&self, name: &str) -> Option<Result<T>>;
+
+    /// Returns the value of the `Content-Length` header. If header is not present or invalid, `None` is returned
+    fn content_length(&self) -> Option<u64>;
+
+    /// Returns the body of the request decoded as UTF-8 text.
+    ///
+    /// The `charset` parameter of `Content-Type` is honored: bodies declaring a charset other than
+    /// `utf-8` (or its subset `us-ascii`) are rejected. An error is also returned if the body isn't valid UTF-8
+    ///
+    /// Requires the `anyhow` feature (enabled by default)
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::RequestBuilder;
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// let fake_req = RequestBuilder::new(spin_sdk::http::Method::Post, "http://foo/bar")
+    ///    .header("content-type", "text/plain; charset=UTF-8")
+    ///    .body("Hello, Spin").build();
+    ///
+    /// assert_eq!(fake_req.body_text().unwrap(), "Hello, Spin");
+    /// ```
+    #[cfg(feature = "anyhow")]
+    fn body_text(&self) -> Result<String>;
+
+    /// Deserializes the JSON body of the request, after checking its content type and size.
+    ///
+    /// Returns the error response to send, which allows using `?` in handlers:
+    /// `415 Unsupported Media Type` if `Content-Type` is not JSON (`application/json` or `+json`),
+    /// `413 Payload Too Large` if the body (or `Content-Length`) exceeds `max_bytes` and
+    /// `400 Bad Request` if the body can't be deserialized
+    ///
+    /// Requires the `json` feature
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::{Request, Response};
+    /// use spin_contrib_http::request::Contrib;
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Todo {
+    ///     title: String,
+    /// }
+    ///
+    /// fn handler(req: Request) -> Result<Response, Response> {
+    ///     let todo: Todo = req.json_body_guarded(16 * 1024)?;
+    ///     Ok(Response::new(201, todo.title))
+    /// }
+    /// ```
+    #[cfg(feature = "json")]
+    fn json_body_guarded<T: serde::de::DeserializeOwned>(
+        &self,
+        max_bytes: usize,
+    ) -> std::result::Result<T, Response>;
+
+    /// Determines if the request method is `GET`
+    fn is_get(&self) -> bool;
+
+    /// Determines if the request method is `POST`
+    fn is_post(&self) -> bool;
+
+    /// Determines if the request method is `PUT`
+    fn is_put(&self) -> bool;
+
+    /// Determines if the request method is `PATCH`
+    fn is_patch(&self) -> bool;
+
+    /// Determines if the request method is `DELETE`
+    fn is_delete(&self) -> bool;
+
+    /// Determines if the request method is `OPTIONS`
+    fn is_options(&self) -> bool;
+
+    /// Determines if the request method is `HEAD`
+    fn is_head(&self) -> bool;
+}
+
+impl Contrib for Request {
+    fn get_path(&self) -> String {
+        let path = match self.header(HEADER_SPIN_PATH_INFO) {
+            Some(path_info) => path_info.as_str().unwrap_or_default(),
+            None => self.path(),
+        };
+        if path.is_empty() {
+            return "/".to_string();
+        }
+        percent_decode_str(path).decode_utf8_lossy().to_string()
+    }
+
+    fn get_path_and_query(&self) -> String {
+        match self.query_string() {
+            Some(query) => format!("{}?{}", self.get_path(), query),
+            None => self.get_path(),
+        }
+    }
+
+    fn get_route_segments(&self) -> Option<Vec<&str>> {
+        let spin_path_header_value = self.header(HEADER_SPIN_PATH_INFO)?;
+        let header_value = spin_path_header_value.as_str()?;
+        if header_value.trim().is_empty() || header_value == "/" {
+            return None;
+        };
+
+        let mut segments = header_value.split('/').collect::<Vec<&str>>();
+        segments.remove(0);
+        Some(segments)
+    }
+
+    fn get_route_segments_owned(&self) -> Option<Vec<String>> {
+        self.get_route_segments()
+            .map(|segments| segments.into_iter().map(str::to_string).collect())
+    }
+
+    fn get_route_segment(&self, index: usize) -> Option<&str> {
+        self.get_route_segments()?.get(index).copied()
+    }
+
+    fn match_route(&self, template: &str) -> Option<HashMap<String, String>> {
+        let path = self.get_route_segments().unwrap_or_default().join("/");
+        match_path_template(&path, template)
+    }
+
+    fn is_preflight_request(&self) -> bool {
+        is_preflight(
+            self.method(),
+            &self.get_header_value_as_string(http::header::ORIGIN.as_str()),
+            &self.get_header_value_as_string(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str()),
+        )
+    }
+
+    fn preflight_details(&self) -> Option<PreflightRequest> {
+        if !self.is_preflight_request() {
+            return None;
+        }
+        let requested_headers = self
+            .get_header_value_as_string(http::header::ACCESS_CONTROL_REQUEST_HEADERS.as_str())
+            .split(',')
+            .map(|header| header.trim().to_lowercase())
+            .filter(|header| !header.is_empty())
+            .collect();
+        Some(PreflightRequest {
+            origin: normalize_origin(
+                &self.get_header_value_as_string(http::header::ORIGIN.as_str()),
+            ),
+            requested_method: self
+                .get_header_value_as_string(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str())
+                .trim()
+                .to_uppercase(),
+            requested_headers,
+        })
+    }
+
+    fn get_header_ci(&self, name: &str) -> Option<String> {
+        self.header(name)
+            .or_else(|| {
+                self.headers()
+                    .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value)
+            })
+            .and_then(|value| value.as_str())
+            .map(str::to_string)
+    }
+
+    fn get_header_value_as_string(&self, header_name: &str) -> String {
+        self.header(header_name)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    fn header_contains_token(&self, name: &str, token: &str) -> bool {
+        let token = token.trim();
+        self.get_header_value_as_string(name)
+            .split(',')
+            .map(|element| element.split('=').next().unwrap_or_default().trim())
+            .any(|element| !element.is_empty() && element.eq_ignore_ascii_case(token))
+    }
+
+    fn forwarded(&self) -> Vec<ForwardedElement> {
+        let header_value = self.get_header_value_as_string(HEADER_FORWARDED);
+        split_unquoted(&header_value, ',')
+            .into_iter()
+            .filter(|element| !element.trim().is_empty())
+            .map(|element| {
+                let mut forwarded = ForwardedElement::default();
+                for pair in split_unquoted(element, ';') {
+                    let Some((key, value)) = pair.split_once('=') else {
+                        continue;
+                    };
+                    let value = Some(unquote(value.trim()));
+                    match key.trim().to_lowercase().as_str() {
+                        "for" => forwarded.for_ = value,
+                        "proto" => forwarded.proto = value,
+                        "by" => forwarded.by = value,
+                        "host" => forwarded.host = value,
+                        _ => {}
+                    }
+                }
+                forwarded
+            })
+            .collect()
+    }
+
+    fn preferred_language(&self, supported: &[&str]) -> Option<String> {
+        let header_value = self.get_header_value_as_string(http::header::ACCEPT_LANGUAGE.as_str());
+        // `*` only matches languages which aren't covered by a listed range (including those excluded using `q=0`)
+        let listed = parse_quality_values(&header_value);
+        for (tag, _) in parse_quality_list(&header_value) {
+            if tag == "*" {
+                if let Some(matched) = supported.iter().find(|candidate| {
+                    !listed.iter().any(|(range, _)| {
+                        range.eq_ignore_ascii_case(candidate)
+                            || is_language_prefix(range, candidate)
+                    })
+                }) {
+                    return Some(matched.to_string());
+                }
+                continue;
+            }
+            let matched = supported.iter().find(|candidate| {
+                candidate.eq_ignore_ascii_case(&tag)
+                    || is_language_prefix(candidate, &tag)
+                    || is_language_prefix(&tag, candidate)
+            });
+            if let Some(matched) = matched {
+                return Some(matched.to_string());
+            }
+        }
+        None
+    }
+
+    fn preferred_charset(&self, supported: &[&str]) -> Option<String> {
+        let header_value = self.get_header_value_as_string(http::header::ACCEPT_CHARSET.as_str());
+        if header_value.trim().is_empty() {
+            return supported.first().map(|s| s.to_string());
+        }
+        // `*` only matches charsets which aren't listed explicitly (including those excluded using `q=0`)
+        let listed = parse_quality_values(&header_value);
+        for (charset, _) in parse_quality_list(&header_value) {
+            if charset == "*" {
+                if let Some(matched) = supported.iter().find(|candidate| {
+                    !listed
+                        .iter()
+                        .any(|(listed, _)| listed.eq_ignore_ascii_case(candidate))
+                }) {
+                    return Some(matched.to_string());
+                }
+                continue;
+            }
+            if let Some(matched) = supported
+                .iter()
+                .find(|candidate| candidate.eq_ignore_ascii_case(&charset))
+            {
+                return Some(matched.to_string());
+            }
+        }
+        None
+    }
+
+    fn accepts_encoding(&self, coding: &str) -> bool {
+        let Some(header_value) = self.header(http::header::ACCEPT_ENCODING.as_str()) else {
+            return true;
+        };
+        let values = parse_quality_values(header_value.as_str().unwrap_or_default());
+        let quality_of = |name: &str| {
+            values
+                .iter()
+                .find(|(value, _)| value.eq_ignore_ascii_case(name))
+                .map(|(_, quality)| *quality)
+        };
+        let coding = coding.trim();
+        match quality_of(coding).or_else(|| quality_of("*")) {
+            Some(quality) => quality > 0.0,
+            None => coding.eq_ignore_ascii_case("identity"),
+        }
+    }
+
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        parse_socket_addr(&self.get_header_value_as_string(HEADER_SPIN_CLIENT_ADDR))
+            .or_else(|| {
+                self.forwarded()
+                    .into_iter()
+                    .next()
+                    .and_then(|element| element.for_)
+                    .and_then(|for_| parse_socket_addr(&for_))
+            })
+            .or_else(|| {
+                parse_socket_addr(&first_list_value(
+                    &self.get_header_value_as_string(HEADER_X_FORWARDED_FOR),
+                ))
+            })
+    }
+
+    fn host(&self) -> Option<String> {
+        [http::header::HOST.as_str(), HEADER_X_FORWARDED_HOST]
+            .iter()
+            .map(|name| first_list_value(&self.get_header_value_as_string(name)))
+            .find(|value| !value.is_empty())
+    }
+
+    fn is_secure(&self) -> bool {
+        self.scheme() == "https"
+    }
+
+    fn scheme(&self) -> String {
+        let scheme = first_list_value(&self.get_header_value_as_string(HEADER_X_FORWARDED_PROTO));
+        if scheme.is_empty() {
+            return "http".to_string();
+        }
+        scheme.to_lowercase()
+    }
+
+    fn if_none_match_matches(&self, etag: &str) -> bool {
+        let header_value = self.get_header_value_as_string(http::header::IF_NONE_MATCH.as_str());
+        etag_list_matches(&header_value, etag, false)
+    }
+
+    fn if_match_matches(&self, etag: &str) -> bool {
+        let header_value = self.get_header_value_as_string(http::header::IF_MATCH.as_str());
+        etag_list_matches(&header_value, etag, true)
+    }
+
+    fn if_range_satisfied(&self, current_etag: &str, last_modified: Option<SystemTime>) -> bool {
+        let header_value = self.get_header_value_as_string(http::header::IF_RANGE.as_str());
+        let validator = header_value.trim();
+        if validator.is_empty() {
+            return true;
+        }
+        if validator.starts_with('"') || validator.starts_with("W/") {
+            return etag_list_matches(validator, current_etag, true);
+        }
+        match (parse_http_date(validator), last_modified) {
+            (Some(date), Some(last_modified)) => {
+                let secs = |time: SystemTime| {
+                    time.duration_since(UNIX_EPOCH)
+                        .map(|duration| duration.as_secs())
+                        .ok()
+                };
+                secs(date) == secs(last_modified)
+            }
+            _ => false,
+        }
+    }
+
+    fn user_agent(&self) -> Option<String> {
+        non_empty(self.get_header_value_as_string(http::header::USER_AGENT.as_str()))
+    }
+
+    fn referer(&self) -> Option<String> {
+        non_empty(self.get_header_value_as_string(http::header::REFERER.as_str()))
+    }
+
+    fn origin(&self) -> Option<String> {
+        non_empty(normalize_origin(
+            &self.get_header_value_as_string(http::header::ORIGIN.as_str()),
+        ))
+    }
+
+    fn is_ajax(&self) -> bool {
+        self.get_header_value_as_string(HEADER_X_REQUESTED_WITH)
+            .trim()
+            .eq_ignore_ascii_case("XMLHttpRequest")
+    }
+
+    fn query_string(&self) -> Option<&str> {
+        let (_, query) = self.uri().split_once('?')?;
+        let query = query.split('#').next().unwrap_or_default();
+        (!query.is_empty()).then_some(query)
+    }
+
+    fn prefers_json(&self) -> bool {
+        let accept = self.get_header_value_as_string(http::header::ACCEPT.as_str());
+        let mut json_quality: Option<f32> = None;
+        let mut html_quality = 0.0_f32;
+        for (value, quality) in parse_quality_list(&accept) {
+            let Some(media_type) = MediaType::parse(&value) else {
+                continue;
+            };
+            if media_type.is_json() {
+                json_quality = Some(json_quality.unwrap_or_default().max(quality));
+            } else if ["text/html", "application/xhtml+xml"]
+                .contains(&media_type.essence().as_str())
+            {
+                html_quality = html_quality.max(quality);
+            }
+        }
+        json_quality.is_some_and(|json_quality| json_quality >= html_quality)
+    }
+
+    fn is_same_origin(&self, expected_host: &str) -> bool {
+        if self.origin().as_deref() == Some(NULL_ORIGIN) {
+            return false;
+        }
+        let source = self
+            .origin()
+            .or_else(|| self.referer())
+            .and_then(|source| normalized_authority(&source));
+        match (source, normalized_authority(expected_host)) {
+            (Some(source), Some(expected)) => source == expected,
+            _ => false,
+        }
+    }
+
+    fn pagination(&self, defaults: Pagination, max_per_page: u32) -> Pagination {
+        let query = self.query();
+        let numeric_param = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| query_param(query, name))
+                .and_then(|value| value.trim().parse::<u32>().ok())
+        };
+
+        let per_page = numeric_param(&["per_page", "limit"])
+            .unwrap_or(defaults.per_page)
+            .clamp(1, max_per_page.max(1));
+        let page = numeric_param(&["page"])
+            .or_else(|| numeric_param(&["offset"]).map(|offset| offset / per_page + 1))
+            .unwrap_or(defaults.page)
+            .max(1);
+        Pagination { page, per_page }
+    }
+
+    #[cfg(feature = "uuid")]
+    fn request_id(&self) -> String {
+        non_empty(self.get_header_value_as_string(HEADER_X_REQUEST_ID))
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+    }
+
+    fn parse_cookies(&self) -> Vec<(String, String)> {
+        cookie_pairs(&self.get_header_value_as_string(http::header::COOKIE.as_str()))
+            .map(|(name, value)| (name.to_string(), decode_cookie_value(value)))
+            .collect()
+    }
+
+    fn get_cookie_as_bool(&self, name: &str) -> Option<bool> {
+        match self.get_cookie(name)?.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" => Some(true),
+            "false" | "0" | "no" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn get_cookie_parsed<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get_cookie(name)?.trim().parse().ok()
+    }
+
+    fn has_cookie(&self, name: &str) -> bool {
+        cookie_pairs(&self.get_header_value_as_string(http::header::COOKIE.as_str()))
+            .any(|(cookie_name, _)| cookie_name == name)
+    }
+
+    fn cookie_count(&self) -> usize {
+        cookie_pairs(&self.get_header_value_as_string(http::header::COOKIE.as_str())).count()
+    }
+
+    fn get_cookie(&self, name: &str) -> Option<String> {
+        self.parse_cookies()
+            .into_iter()
+            .find(|(cookie_name, _)| cookie_name == name)
+            .map(|(_, value)| value)
+    }
+
+    #[cfg(all(feature = "json", feature = "anyhow"))]
+    fn get_cookie_as<T: serde::de::DeserializeOwned>(&self, name: &str) -> Option<Result<T>> {
+        let value = self.get_cookie(name)?;
+        Some(deserialize_cookie_value(&value))
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        self.get_header_value_as_string(http::header::CONTENT_LENGTH.as_str())
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    #[cfg(feature = "anyhow")]
+    fn body_text(&self) -> Result<String> {
+        let content_type = self.get_header_value_as_string(http::header::CONTENT_TYPE.as_str());
+        if let Some(charset) =
+            MediaType::parse(&content_type).and_then(|m| m.parameter("charset").map(str::to_string))
+        {
+            if !["utf-8", "utf8", "us-ascii"].contains(&charset.to_lowercase().as_str()) {
+                bail!("unsupported request body charset: {}", charset);
+            }
+        }
+        String::from_utf8(self.body().to_vec())
+            .map_err(|e| anyhow!("request body is not valid UTF-8: {}", e.utf8_error()))
+    }
+
+    #[cfg(feature = "json")]
+    fn json_body_guarded<T: serde::de::DeserializeOwned>(
+        &self,
+        max_bytes: usize,
+    ) -> std::result::Result<T, Response> {
+        let content_type = self.get_header_value_as_string(http::header::CONTENT_TYPE.as_str());
+        if !MediaType::parse(&content_type).is_some_and(|m| m.is_json()) {
+            return Err(Response::new(http::StatusCode::UNSUPPORTED_MEDIA_TYPE, ()));
+        }
+        let declared_too_large = self
+            .content_length()
+            .is_some_and(|length| length > max_bytes as u64);
+        if declared_too_large || self.body().len() > max_bytes {
+            return Err(Response::new(http::StatusCode::PAYLOAD_TOO_LARGE, ()));
+        }
+        crate::response::or_bad_request(serde_json::from_slice(self.body()))
+    }
+
+    fn is_get(&self) -> bool {
+        self.method() == &Method::Get
+    }
+
+    fn is_post(&self) -> bool {
+        self.method() == &Method::Post
+    }
+
+    fn is_put(&self) -> bool {
+        self.method() == &Method::Put
+    }
+
+    fn is_patch(&self) -> bool {
+        self.method() == &Method::Patch
+    }
+
+    fn is_delete(&self) -> bool {
+        self.method() == &Method::Delete
+    }
+
+    fn is_options(&self) -> bool {
+        self.method() == &Method::Options
+    }
+
+    fn is_head(&self) -> bool {
+        self.method() == &Method::Head
+    }
+}
+
+/// Splits a `Cookie` header value into (name, raw value) pairs, skipping malformed pairs
+fn cookie_pairs(header_value: &str) -> impl Iterator<Item = (&str, &str)> {
+    header_value.split(';').filter_map(|pair| {
+        let (name, value) = pair.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        Some((name, value.trim().trim_matches('"')))
+    })
+}
+
+/// Reads a streamed request body, aborting as soon as it exceeds `max_bytes`
+///
+/// Spin only streams request bodies to handlers accepting `spin_sdk::http::IncomingRequest`
+/// (use `IncomingRequest::into_body_stream()`). The body of a `spin_sdk::http::Request` is already buffered,
+/// use `content_length` to reject oversized bodies of those early.
+/// Returns `ContribError::PayloadTooLarge` (which converts into `413 Payload Too Large`) once the limit is exceeded,
+/// without reading the remaining chunks. Errors of the stream are returned as `ContribError::Stream`
+///
+/// # Example
+/// ```rust,no_run
+/// use spin_sdk::http::{IncomingRequest, ResponseOutparam};
+/// use spin_contrib_http::request::body_bytes_limited;
+///
+/// async fn handler(req: IncomingRequest, response_out: ResponseOutparam) {
+///     match body_bytes_limited(req.into_body_stream(), 1024 * 1024).await {
+///         Ok(body) => { /* process the body */ }
+///         Err(e) => { /* respond with e.status_code() */ }
+///     }
+/// }
+/// ```
+pub async fn body_bytes_limited<S, E>(stream: S, max_bytes: usize) -> Result<Vec<u8>, ContribError>
+where
+    S: futures::Stream<Item = std::result::Result<Vec<u8>, E>>,
+    E: std::fmt::Debug,
+{
+    use futures::StreamExt;
+
+    let mut stream = std::pin::pin!(stream);
+    let mut body = vec![];
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| {
+            ContribError::Stream(format!("reading the request body failed: {:?}", e))
+        })?;
+        if body.len() + chunk.len() > max_bytes {
+            return Err(ContribError::PayloadTooLarge(max_bytes));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+#[cfg(all(feature = "json", feature = "anyhow"))]
+fn deserialize_cookie_value<T: serde::de::DeserializeOwned>(value: &str) -> Result<T> {
+    use base64::Engine;
+
+    let json_error = match serde_json::from_str(value) {
+        Ok(deserialized) => return Ok(deserialized),
+        Err(e) => e,
+    };
+    let decoded = [
+        base64::engine::general_purpose::STANDARD,
+        base64::engine::general_purpose::URL_SAFE,
+        base64::engine::general_purpose::STANDARD_NO_PAD,
+        base64::engine::general_purpose::URL_SAFE_NO_PAD,
+    ]
+    .iter()
+    .find_map(|engine| engine.decode(value).ok());
+    match decoded {
+        Some(decoded) => Ok(serde_json::from_slice(&decoded)?),
+        None => Err(json_error.into()),
+    }
+}
+
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode_str(key).decode_utf8_lossy() == name)
+                .then(|| percent_decode_str(value).decode_utf8_lossy().to_string())
+        })
+        .next()
+}
+
+/// Matches the path against the template (see `Contrib::match_route`) and extracts named parameters
+pub(crate) fn match_path_template(path: &str, template: &str) -> Option<HashMap<String, String>> {
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<&str>>();
+    let template = template
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<&str>>();
+
+    let mut params = HashMap::new();
+    for (index, part) in template.iter().enumerate() {
+        if *part == "*" && index == template.len() - 1 {
+            params.insert("*".to_string(), segments.get(index..)?.join("/"));
+            return Some(params);
+        }
+        let segment = segments.get(index)?;
+        match part.strip_prefix(':') {
+            Some(name) => {
+                params.insert(name.to_string(), segment.to_string());
+            }
+            None if part == segment => {}
+            None => return None,
+        }
+    }
+    (segments.len() == template.len()).then_some(params)
+}
+
+/// Parses a socket address with optional port (`1.2.3.4`, `1.2.3.4:80`, `2001:db8::1`, `[2001:db8::1]:443`)
+fn parse_socket_addr(value: &str) -> Option<SocketAddr> {
+    let value = value.trim();
+    if let Ok(addr) = value.parse::<SocketAddr>() {
+        return Some(addr);
+    }
+    let ip = value.trim_start_matches('[').trim_end_matches(']');
+    ip.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 0))
+}
+
+/// Returns the lowercase `host[:port]` of the provided URL or authority, omitting default ports
+fn normalized_authority(value: &str) -> Option<String> {
+    let value = value.trim();
+    let uri = if value.contains("://") {
+        value.parse::<http::Uri>().ok()?
+    } else {
+        format!("http://{}", value).parse::<http::Uri>().ok()?
+    };
+    let host = uri.host()?.to_lowercase();
+    let default_port = match uri.scheme_str() {
+        Some("https") => 443,
+        _ => 80,
+    };
+    match uri.port_u16() {
+        Some(port) if port != default_port => Some(format!("{}:{}", host, port)),
+        _ => Some(host),
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    Some(value.to_string())
+}
+
+fn etag_list_matches(header_value: &str, etag: &str, strong: bool) -> bool {
+    let (etag_is_weak, etag) = split_etag(etag);
+    header_value.split(',').any(|candidate| {
+        let candidate = candidate.trim();
+        if candidate == "*" {
+            return true;
+        }
+        let (candidate_is_weak, candidate) = split_etag(candidate);
+        if strong && (etag_is_weak || candidate_is_weak) {
+            return false;
+        }
+        !candidate.is_empty() && candidate == etag
+    })
+}
+
+fn split_etag(etag: &str) -> (bool, String) {
+    let etag = etag.trim();
+    let (weak, opaque) = match etag.strip_prefix("W/") {
+        Some(opaque) => (true, opaque),
+        None => (false, etag),
+    };
+    (weak, opaque.trim_matches('"').to_string())
+}
+
+fn first_list_value(header_value: &str) -> String {
+    header_value
+        .split(',')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+fn is_language_prefix(prefix: &str, tag: &str) -> bool {
+    tag.len() > prefix.len()
+        && tag.as_bytes()[prefix.len()] == b'-'
+        && tag[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+/// Parses a header like `Accept-Language` into its values ordered by descending quality.
+///
+/// Values with a quality of 0 are not acceptable and therefore omitted
+pub(crate) fn parse_quality_list(header_value: &str) -> Vec<(String, f32)> {
+    let mut values: Vec<(String, f32)> = parse_quality_values(header_value)
+        .into_iter()
+        .filter(|(_, quality)| *quality > 0.0)
+        .collect();
+    values.sort_by(|a, b| b.1.total_cmp(&a.1));
+    values
+}
+
+/// Parses a comma-separated list with quality values, keeping the order and values with `q=0`
+fn parse_quality_values(header_value: &str) -> Vec<(String, f32)> {
+    header_value
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let value = parts.next()?.trim();
+            if value.is_empty() {
+                return None;
+            }
+            let quality = parts
+                .filter_map(|param| param.split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("q"))
+                .map(|(_, q)| q.trim().parse::<f32>().unwrap_or(0.0))
+                .unwrap_or(1.0);
+            Some((value.to_string(), quality))
+        })
+        .collect()
+}
+
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut in_quotes = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (idx, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            c if c == separator && !in_quotes => {
+                parts.push(&value[start..idx]);
+                start = idx + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+fn unquote(value: &str) -> String {
+    if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return value.to_string();
+    }
+    let mut unquoted = String::with_capacity(value.len() - 2);
+    let mut escaped = false;
+    for c in value[1..value.len() - 1].chars() {
+        if c == '\\' && !escaped {
+            escaped = true;
+            continue;
+        }
+        escaped = false;
+        unquoted.push(c);
+    }
+    unquoted
+}
+
+#[cfg(test)]
+mod tests {
+    use spin_sdk::http::{Method, RequestBuilder};
+
+    use super::*;
+
+    #[test]
+    fn get_route_segments_should_return_provided_segments() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(HEADER_SPIN_PATH_INFO, "/foo/bar/baz")
+            .body(())
+            .build();
+        let segments = req.get_route_segments();
+        assert!(segments.is_some());
+        let segments = segments.unwrap();
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0], "foo");
+        assert_eq!(segments[1], "bar");
+        assert_eq!(segments[2], "baz");
+    }
+
+    #[test]
+    fn get_route_segments_should_empty_vector_for_root_url() {
+        let test_data = vec!["/", "", " "];
+        for data in test_data {
+            let req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo.bar")
+                .header(HEADER_SPIN_PATH_INFO, data)
+                .body(())
+                .build();
+
+            assert_eq!(req.get_route_segments(), None);
+        }
+    }
+
+    #[test]
+    fn get_route_segment_should_return_segment_at_index() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(HEADER_SPIN_PATH_INFO, "/foo/bar/baz")
+            .body(())
+            .build();
+
+        assert_eq!(req.get_route_segment(0), Some("foo"));
+        assert_eq!(req.get_route_segment(1), Some("bar"));
+        assert_eq!(req.get_route_segment(3), None);
+
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(HEADER_SPIN_PATH_INFO, "/")
+            .body(())
+            .build();
+        assert_eq!(req.get_route_segment(0), None);
+    }
+
+    #[test]
+    fn get_header_value_as_string_should_return_correct_values() {
+        let test_data = vec![
+            ("my-header", "foo", "my-header", "foo"),
+            ("my-header", "", "my-header", ""),
+            ("my-header", "foo", "my-other-header", ""),
+            ("my-header", "", "my-other-header", ""),
+        ];
+        for data in test_data {
+            let req = RequestBuilder::new(spin_sdk::http::Method::Get, "http://foo.bar")
+                .header(data.0, data.1)
+                .body(())
+                .build();
+
+            assert_eq!(req.get_header_value_as_string(data.2), data.3);
+        }
+    }
+
+    #[test]
+    fn get_header_value_as_string_should_not_panic_for_non_utf8_values() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .headers(vec![(
+                http::header::USER_AGENT.to_string(),
+                vec![b'M', 0xE9],
+            )])
+            .body(())
+            .build();
+
+        assert_eq!(
+            req.get_header_value_as_string(http::header::USER_AGENT.as_str()),
+            ""
+        );
+        assert_eq!(req.user_agent(), None);
+    }
+
+    #[test]
+    fn forwarded_should_parse_multiple_elements() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(
+                HEADER_FORWARDED,
+                "for=192.0.2.60;proto=http;by=203.0.113.43, for=198.51.100.17;host=example.com",
+            )
+            .body(())
+            .build();
+
+        let sut = req.forwarded();
+        assert_eq!(sut.len(), 2);
+        assert_eq!(
+            sut[0],
+            ForwardedElement {
+                for_: Some("192.0.2.60".to_string()),
+                proto: Some("http".to_string()),
+                by: Some("203.0.113.43".to_string()),
+                host: None,
+            }
+        );
+        assert_eq!(
+            sut[1],
+            ForwardedElement {
+                for_: Some("198.51.100.17".to_string()),
+                proto: None,
+                by: None,
+                host: Some("example.com".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn forwarded_should_unquote_ipv6_values() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(HEADER_FORWARDED, "For=\"[2001:db8::1]\";proto=https")
+            .body(())
+            .build();
+
+        let sut = req.forwarded();
+        assert_eq!(sut.len(), 1);
+        assert_eq!(sut[0].for_.as_deref(), Some("[2001:db8::1]"));
+        assert_eq!(sut[0].proto.as_deref(), Some("https"));
+    }
+
+    #[test]
+    fn forwarded_should_return_empty_vector_if_header_is_missing() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .body(())
+            .build();
+
+        assert!(req.forwarded().is_empty());
+    }
+
+    #[test]
+    fn preferred_language_should_honor_quality_and_prefix_matches() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(
+                http::header::ACCEPT_LANGUAGE.as_str(),
+                "de;q=0.7, en-US;q=0.9",
+            )
+            .body(())
+            .build();
+
+        assert_eq!(
+            req.preferred_language(&["en", "fr"]),
+            Some("en".to_string())
+        );
+        assert_eq!(
+            req.preferred_language(&["fr", "de"]),
+            Some("de".to_string())
+        );
+        assert_eq!(req.preferred_language(&["fr"]), None);
+    }
+
+    #[test]
+    fn preferred_language_should_fall_back_to_first_supported_for_wildcard() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(http::header::ACCEPT_LANGUAGE.as_str(), "fr, *;q=0.1")
+            .body(())
+            .build();
+
+        assert_eq!(
+            req.preferred_language(&["en", "de"]),
+            Some("en".to_string())
+        );
+    }
+
+    #[test]
+    fn preferred_language_wildcard_should_skip_excluded_languages() {
+        let test_data = vec![
+            ("en;q=0, *", vec!["en", "de"], Some("de")),
+            ("en;q=0, *", vec!["en-GB", "de"], Some("de")),
+            ("en;q=0, *", vec!["en"], None),
+            ("*, en;q=0.5", vec!["en", "de"], Some("de")),
+            ("*, en;q=0.5", vec!["en"], Some("en")),
+        ];
+        for (accept_language, supported, expected) in test_data {
+            let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+                .header(http::header::ACCEPT_LANGUAGE.as_str(), accept_language)
+                .body(())
+                .build();
+            assert_eq!(
+                req.preferred_language(&supported).as_deref(),
+                expected,
+                "Accept-Language: {}",
+                accept_language
+            );
+        }
+    }
+
+    #[test]
+    fn host_should_prefer_host_header_and_keep_port() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(http::header::HOST.as_str(), "example.com:8080")
+            .header(HEADER_X_FORWARDED_HOST, "proxy.example.com")
+            .body(())
+            .build();
+        assert_eq!(req.host(), Some("example.com:8080".to_string()));
+
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(HEADER_X_FORWARDED_HOST, "example.com, proxy.example.com")
+            .body(())
+            .build();
+        assert_eq!(req.host(), Some("example.com".to_string()));
+
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .body(())
+            .build();
+        assert_eq!(req.host(), None);
+    }
+
+    #[test]
+    fn is_secure_should_check_forwarded_proto() {
+        let test_data = vec![
+            (Some("https"), true),
+            (Some("HTTPS"), true),
+            (Some("https, http"), true),
+            (Some("http"), false),
+            (None, false),
+        ];
+        for (proto, expected) in test_data {
+            let mut builder = RequestBuilder::new(Method::Get, "http://foo.bar");
+            if let Some(proto) = proto {
+                builder.header(HEADER_X_FORWARDED_PROTO, proto);
+            }
+            let req = builder.body(()).build();
+            assert_eq!(req.is_secure(), expected, "X-Forwarded-Proto: {:?}", proto);
+        }
+    }
+
+    #[test]
+    fn if_none_match_matches_should_use_weak_comparison() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(http::header::IF_NONE_MATCH.as_str(), "\"abc\", W/\"def\"")
+            .body(())
+            .build();
+
+        let test_data = vec![
+            ("\"abc\"", true),
+            ("W/\"abc\"", true),
+            ("\"def\"", true),
+            ("W/\"def\"", true),
+            ("abc", true),
+            ("\"xyz\"", false),
+            ("", false),
+        ];
+        for (etag, expected) in test_data {
+            assert_eq!(req.if_none_match_matches(etag), expected, "ETag: {}", etag);
+        }
+    }
+
+    #[test]
+    fn if_match_matches_should_use_strong_comparison() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(http::header::IF_MATCH.as_str(), "\"abc\", W/\"def\"")
+            .body(())
+            .build();
+
+        let test_data = vec![
+            ("\"abc\"", true),
+            ("W/\"abc\"", false),
+            ("\"def\"", false),
+            ("\"xyz\"", false),
+        ];
+        for (etag, expected) in test_data {
+            assert_eq!(req.if_match_matches(etag), expected, "ETag: {}", etag);
+        }
+    }
+
+    #[test]
+    fn etag_helpers_should_handle_wildcard_and_missing_header() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(http::header::IF_MATCH.as_str(), "*")
+            .header(http::header::IF_NONE_MATCH.as_str(), "*")
+            .body(())
+            .build();
+        assert!(req.if_match_matches("\"abc\""));
+        assert!(req.if_none_match_matches("\"abc\""));
+
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .body(())
+            .build();
+        assert!(!req.if_match_matches("\"abc\""));
+        assert!(!req.if_none_match_matches("\"abc\""));
+    }
+
+    #[test]
+    fn typed_header_accessors_should_return_trimmed_values() {
+        let test_data = vec![
+            (Some(" curl/8.4.0 "), Some("curl/8.4.0")),
+            (Some(""), None),
+            (Some("  "), None),
+            (None, None),
+        ];
+        for (value, expected) in test_data {
+            let mut builder = RequestBuilder::new(Method::Get, "http://foo.bar");
+            if let Some(value) = value {
+                builder
+                    .header(http::header::USER_AGENT.as_str(), value)
+                    .header(http::header::REFERER.as_str(), value);
+            }
+            let req = builder.body(()).build();
+            let expected = expected.map(|e| e.to_string());
+            assert_eq!(req.user_agent(), expected);
+            assert_eq!(req.referer(), expected);
+        }
+    }
+
+    #[test]
+    fn origin_should_be_normalized_like_cors() {
+        let test_data = vec![
+            (
+                Some(" HTTP://Localhost:4200 "),
+                Some("http://localhost:4200"),
+            ),
+            (Some(""), None),
+            (None, None),
+        ];
+        for (value, expected) in test_data {
+            let mut builder = RequestBuilder::new(Method::Get, "http://foo.bar");
+            if let Some(value) = value {
+                builder.header(http::header::ORIGIN.as_str(), value);
+            }
+            let req = builder.body(()).build();
+            assert_eq!(req.origin(), expected.map(|e| e.to_string()));
+        }
+    }
+
+    #[test]
+    fn pagination_should_clamp_per_page() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar/items?page=2&per_page=500")
+            .body(())
+            .build();
+
+        let sut = req.pagination(Pagination::default(), 100);
+        assert_eq!(
+            sut,
+            Pagination {
+                page: 2,
+                per_page: 100
+            }
+        );
+        assert_eq!(sut.offset(), 100);
+    }
+
+    #[test]
+    fn pagination_should_fall_back_to_defaults() {
+        let defaults = Pagination {
+            page: 1,
+            per_page: 25,
+        };
+        let test_data = vec![
+            ("http://foo.bar/items", 1, 25),
+            ("http://foo.bar/items?page=abc&per_page=-1", 1, 25),
+            ("http://foo.bar/items?page=0&per_page=0", 1, 1),
+            ("http://foo.bar/items?limit=10&offset=30", 4, 10),
+        ];
+        for (uri, page, per_page) in test_data {
+            let req = RequestBuilder::new(Method::Get, uri).body(()).build();
+            assert_eq!(
+                req.pagination(defaults, 50),
+                Pagination { page, per_page },
+                "URI: {}",
+                uri
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn request_id_should_preserve_provided_header() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(HEADER_X_REQUEST_ID, "abc-123")
+            .body(())
+            .build();
+
+        assert_eq!(req.request_id(), "abc-123");
+    }
+
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn request_id_should_be_generated_if_header_is_missing() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .body(())
+            .build();
+
+        let sut = req.request_id();
+        assert!(uuid::Uuid::parse_str(&sut).is_ok());
+        assert_ne!(sut, req.request_id());
+    }
+
+    #[test]
+    fn is_preflight_request_should_require_requested_method() {
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .body(())
+            .build();
+        assert!(!req.is_preflight_request());
+
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), "POST")
+            .body(())
+            .build();
+        assert!(req.is_preflight_request());
+
+        let req = RequestBuilder::new(Method::Post, "http://foo.bar")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), "POST")
+            .body(())
+            .build();
+        assert!(!req.is_preflight_request());
+    }
+
+    #[test]
+    fn parse_cookies_should_preserve_duplicates_and_order() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(
+                http::header::COOKIE.as_str(),
+                "session=a; broken; theme=dark%20mode; session=b",
+            )
+            .body(())
+            .build();
+
+        assert_eq!(
+            req.parse_cookies(),
+            vec![
+                ("session".to_string(), "a".to_string()),
+                ("theme".to_string(), "dark mode".to_string()),
+                ("session".to_string(), "b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_cookies_should_return_empty_vector_without_cookie_header() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .body(())
+            .build();
+
+        assert!(req.parse_cookies().is_empty());
+    }
+
+    #[test]
+    fn method_predicates_should_match_request_method() {
+        type Predicate = fn(&Request) -> bool;
+        let predicates: Vec<(Method, Predicate)> = vec![
+            (Method::Get, |r| r.is_get()),
+            (Method::Post, |r| r.is_post()),
+            (Method::Put, |r| r.is_put()),
+            (Method::Patch, |r| r.is_patch()),
+            (Method::Delete, |r| r.is_delete()),
+            (Method::Options, |r| r.is_options()),
+            (Method::Head, |r| r.is_head()),
+        ];
+        for (method, predicate) in &predicates {
+            let matching = RequestBuilder::new(method.clone(), "http://foo.bar").build();
+            assert!(predicate(&matching), "Method: {}", method);
+
+            let other = if method == &Method::Get {
+                Method::Post
+            } else {
+                Method::Get
+            };
+            let non_matching = RequestBuilder::new(other, "http://foo.bar").build();
+            assert!(!predicate(&non_matching), "Method: {}", method);
+        }
+    }
+
+    #[test]
+    fn content_length_should_parse_header() {
+        let test_data = vec![
+            (Some("42"), Some(42)),
+            (Some("0"), Some(0)),
+            (Some("abc"), None),
+            (Some("-1"), None),
+            (None, None),
+        ];
+        for (value, expected) in test_data {
+            let mut builder = RequestBuilder::new(Method::Post, "http://foo.bar");
+            if let Some(value) = value {
+                builder.header(http::header::CONTENT_LENGTH.as_str(), value);
+            }
+            let req = builder.body(()).build();
+            assert_eq!(
+                req.content_length(),
+                expected,
+                "Content-Length: {:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn get_cookie_should_return_first_matching_cookie() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(
+                http::header::COOKIE.as_str(),
+                "a=1; session=abc; session=def",
+            )
+            .body(())
+            .build();
+
+        assert_eq!(req.get_cookie("session"), Some("abc".to_string()));
+        assert_eq!(req.get_cookie("missing"), None);
+    }
+
+    #[cfg(all(feature = "json", feature = "anyhow"))]
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Preferences {
+        theme: String,
+    }
+
+    #[test]
+    #[cfg(all(feature = "json", feature = "anyhow"))]
+    fn get_cookie_as_should_deserialize_json_and_base64_values() {
+        let test_data = vec![
+            "prefs=%7B%22theme%22%3A%22dark%22%7D",
+            "prefs=eyJ0aGVtZSI6ImRhcmsifQ==",
+            "prefs=eyJ0aGVtZSI6ImRhcmsifQ",
+        ];
+        for cookie in test_data {
+            let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+                .header(http::header::COOKIE.as_str(), cookie)
+                .body(())
+                .build();
+
+            let sut: Preferences = req
+                .get_cookie_as("prefs")
+                .expect("Cookie not present")
+                .expect("Cookie could not be deserialized");
+            assert_eq!(
+                sut,
+                Preferences {
+                    theme: "dark".to_string()
+                }
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "json", feature = "anyhow"))]
+    fn get_cookie_as_should_distinguish_missing_and_malformed_cookies() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(http::header::COOKIE.as_str(), "prefs=not-json")
+            .body(())
+            .build();
+
+        assert!(req.get_cookie_as::<Preferences>("missing").is_none());
+        assert!(req.get_cookie_as::<Preferences>("prefs").unwrap().is_err());
+    }
+
+    #[test]
+    fn match_route_should_extract_named_params() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(HEADER_SPIN_PATH_INFO, "/users/42/posts/7")
+            .body(())
+            .build();
+
+        let sut = req
+            .match_route("/users/:id/posts/:post_id")
+            .expect("Route did not match");
+        assert_eq!(sut.len(), 2);
+        assert_eq!(sut["id"], "42");
+        assert_eq!(sut["post_id"], "7");
+
+        let sut = req
+            .match_route("/users/:id/*")
+            .expect("Route did not match");
+        assert_eq!(sut["id"], "42");
+        assert_eq!(sut["*"], "posts/7");
+    }
+
+    #[test]
+    fn match_route_should_return_none_for_non_matching_templates() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(HEADER_SPIN_PATH_INFO, "/users/42/posts/7")
+            .body(())
+            .build();
+
+        let test_data = vec![
+            "/users/:id",
+            "/orders/:id/posts/:post_id",
+            "/users/:id/posts/:post_id/comments",
+        ];
+        for template in test_data {
+            assert!(req.match_route(template).is_none(), "{}", template);
+        }
+    }
+
+    #[test]
+    fn scheme_should_use_forwarded_proto_and_default_to_http() {
+        let test_data = vec![
+            (Some("https"), "https"),
+            (Some("HTTPS, http"), "https"),
+            (Some("http"), "http"),
+            (None, "http"),
+        ];
+        for (header, expected) in test_data {
+            let mut builder = RequestBuilder::new(Method::Get, "http://foo.bar");
+            if let Some(header) = header {
+                builder.header(HEADER_X_FORWARDED_PROTO, header);
+            }
+            let req = builder.body(()).build();
+            assert_eq!(req.scheme(), expected);
+            assert_eq!(req.is_secure(), expected == "https");
+        }
+    }
+
+    #[test]
+    fn is_ajax_should_check_x_requested_with_case_insensitively() {
+        let test_data = vec![
+            (Some("XMLHttpRequest"), true),
+            (Some("xmlhttprequest"), true),
+            (Some("fetch"), false),
+            (None, false),
+        ];
+        for (header, expected) in test_data {
+            let mut builder = RequestBuilder::new(Method::Get, "http://foo.bar");
+            if let Some(header) = header {
+                builder.header(HEADER_X_REQUESTED_WITH, header);
+            }
+            let req = builder.body(()).build();
+            assert_eq!(req.is_ajax(), expected, "{:?}", header);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "anyhow")]
+    fn body_text_should_decode_utf8_bodies() {
+        let test_data = vec![
+            None,
+            Some("text/plain"),
+            Some("text/plain; charset=\"UTF-8\""),
+        ];
+        for content_type in test_data {
+            let mut builder = RequestBuilder::new(Method::Post, "http://foo.bar");
+            if let Some(content_type) = content_type {
+                builder.header(http::header::CONTENT_TYPE.as_str(), content_type);
+            }
+            let req = builder.body("Grüezi, Spin").build();
+            assert_eq!(req.body_text().unwrap(), "Grüezi, Spin");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "anyhow")]
+    fn body_text_should_reject_invalid_bytes_and_unsupported_charsets() {
+        let req = RequestBuilder::new(Method::Post, "http://foo.bar")
+            .body(vec![0x48, 0xff, 0x69])
+            .build();
+        let sut = req.body_text().unwrap_err();
+        assert!(sut
+            .to_string()
+            .starts_with("request body is not valid UTF-8"));
+
+        let req = RequestBuilder::new(Method::Post, "http://foo.bar")
+            .header(
+                http::header::CONTENT_TYPE.as_str(),
+                "text/plain; charset=iso-8859-1",
+            )
+            .body("Hello")
+            .build();
+        let sut = req.body_text().unwrap_err();
+        assert_eq!(
+            sut.to_string(),
+            "unsupported request body charset: iso-8859-1"
+        );
+    }
+
+    #[test]
+    fn query_string_should_return_raw_query() {
+        let test_data = vec![
+            ("http://foo.bar/baz?a=1&b=2", Some("a=1&b=2")),
+            ("http://foo.bar/baz?sig=a%2Bb&x=", Some("sig=a%2Bb&x=")),
+            ("http://foo.bar/baz?", None),
+            ("http://foo.bar/baz", None),
+        ];
+        for (uri, expected) in test_data {
+            let req = RequestBuilder::new(Method::Get, uri).body(()).build();
+            assert_eq!(req.query_string(), expected, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn is_same_origin_should_compare_origin_or_referer_host() {
+        let test_data = vec![
+            (Some("https://example.com"), None, "example.com", true),
+            (
+                Some("https://EXAMPLE.com:443"),
+                None,
+                "https://example.com",
+                true,
+            ),
+            (
+                Some("http://example.com:8080"),
+                None,
+                "example.com:8080",
+                true,
+            ),
+            (
+                None,
+                Some("https://example.com/form?x=1"),
+                "example.com",
+                true,
+            ),
+            (
+                Some("https://evil.com"),
+                Some("https://example.com/"),
+                "example.com",
+                false,
+            ),
+            (Some("http://example.com:8080"), None, "example.com", false),
+            (Some("null"), None, "example.com", false),
+            (None, None, "example.com", false),
+        ];
+        for (origin, referer, expected_host, expected) in test_data {
+            let mut builder = RequestBuilder::new(Method::Post, "http://foo.bar");
+            if let Some(origin) = origin {
+                builder.header(http::header::ORIGIN.as_str(), origin);
+            }
+            if let Some(referer) = referer {
+                builder.header(http::header::REFERER.as_str(), referer);
+            }
+            let req = builder.body(()).build();
+            assert_eq!(
+                req.is_same_origin(expected_host),
+                expected,
+                "{:?} {:?} {}",
+                origin,
+                referer,
+                expected_host
+            );
+        }
+    }
+
+    #[test]
+    fn prefers_json_should_ignore_wildcards_and_compare_with_html() {
+        let test_data = vec![
+            (
+                Some("text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"),
+                false,
+            ),
+            (Some("application/json"), true),
+            (Some("application/problem+json, text/html;q=0.5"), true),
+            (Some("text/html, application/json"), true),
+            (Some("text/html, application/json;q=0.9"), false),
+            (Some("application/json;q=0"), false),
+            (Some("*/*"), false),
+            (None, false),
+        ];
+        for (accept, expected) in test_data {
+            let mut builder = RequestBuilder::new(Method::Get, "http://foo.bar");
+            if let Some(accept) = accept {
+                builder.header(http::header::ACCEPT.as_str(), accept);
+            }
+            let req = builder.body(()).build();
+            assert_eq!(req.prefers_json(), expected, "{:?}", accept);
+        }
+    }
+
+    #[test]
+    fn header_contains_token_should_compare_list_elements_case_insensitively() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(http::header::CONNECTION.as_str(), "keep-alive, Upgrade")
+            .header(
+                http::header::CACHE_CONTROL.as_str(),
+                "no-cache=\"set-cookie\", max-age=0",
+            )
+            .body(())
+            .build();
+
+        let test_data = vec![
+            (http::header::CONNECTION.as_str(), "upgrade", true),
+            (http::header::CONNECTION.as_str(), "KEEP-ALIVE", true),
+            (http::header::CONNECTION.as_str(), "close", false),
+            (http::header::CONNECTION.as_str(), "keep", false),
+            (http::header::CACHE_CONTROL.as_str(), "no-cache", true),
+            (http::header::CACHE_CONTROL.as_str(), "max-age", true),
+            (http::header::CACHE_CONTROL.as_str(), "no-store", false),
+            (http::header::PRAGMA.as_str(), "no-cache", false),
+        ];
+        for (name, token, expected) in test_data {
+            assert_eq!(
+                req.header_contains_token(name, token),
+                expected,
+                "{}: {}",
+                name,
+                token
+            );
+        }
+    }
+
+    #[test]
+    fn get_route_segments_owned_should_match_borrowed_segments() {
+        let test_data = vec!["/foo/bar/baz", "/foo%20bar/", "/", ""];
+        for path in test_data {
+            let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+                .header(HEADER_SPIN_PATH_INFO, path)
+                .body(())
+                .build();
+
+            let expected = req
+                .get_route_segments()
+                .map(|segments| segments.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+            assert_eq!(req.get_route_segments_owned(), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn remote_addr_should_parse_ipv4_and_ipv6_addresses() {
+        let test_data = vec![
+            (
+                HEADER_SPIN_CLIENT_ADDR,
+                "[2001:db8::1]:443",
+                "[2001:db8::1]:443",
+            ),
+            (
+                HEADER_SPIN_CLIENT_ADDR,
+                "192.0.2.60:54321",
+                "192.0.2.60:54321",
+            ),
+            (
+                HEADER_FORWARDED,
+                "for=\"[2001:db8::1]:443\";proto=https",
+                "[2001:db8::1]:443",
+            ),
+            (
+                HEADER_FORWARDED,
+                "for=192.0.2.60, for=198.51.100.17",
+                "192.0.2.60:0",
+            ),
+            (
+                HEADER_X_FORWARDED_FOR,
+                "2001:db8::2, 198.51.100.17",
+                "[2001:db8::2]:0",
+            ),
+        ];
+        for (header, value, expected) in test_data {
+            let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+                .header(header, value)
+                .body(())
+                .build();
+            let sut = req.remote_addr().expect("Address not parsed");
+            assert_eq!(sut.to_string(), expected, "{}: {}", header, value);
+        }
+    }
+
+    #[test]
+    fn remote_addr_should_prefer_spin_client_addr() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(HEADER_SPIN_CLIENT_ADDR, "127.0.0.1:3000")
+            .header(HEADER_X_FORWARDED_FOR, "192.0.2.60")
+            .body(())
+            .build();
+        assert_eq!(req.remote_addr().unwrap().to_string(), "127.0.0.1:3000");
+
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(HEADER_X_FORWARDED_FOR, "unknown")
+            .body(())
+            .build();
+        assert!(req.remote_addr().is_none());
+    }
+
+    #[test]
+    fn if_range_satisfied_should_compare_validators() {
+        let last_modified = UNIX_EPOCH + std::time::Duration::from_secs(784_111_777);
+        let test_data = vec![
+            (None, true),
+            (Some("\"abc\""), true),
+            (Some("\"def\""), false),
+            (Some("W/\"abc\""), false),
+            (Some("Sun, 06 Nov 1994 08:49:37 GMT"), true),
+            (Some("Sun, 06 Nov 1994 08:49:38 GMT"), false),
+            (Some("not a date"), false),
+        ];
+        for (if_range, expected) in test_data {
+            let mut builder = RequestBuilder::new(Method::Get, "http://foo.bar");
+            builder.header(http::header::RANGE.as_str(), "bytes=0-99");
+            if let Some(if_range) = if_range {
+                builder.header(http::header::IF_RANGE.as_str(), if_range);
+            }
+            let req = builder.body(()).build();
+            assert_eq!(
+                req.if_range_satisfied("\"abc\"", Some(last_modified)),
+                expected,
+                "If-Range: {:?}",
+                if_range
+            );
+        }
+    }
+
+    #[test]
+    fn if_range_satisfied_should_reject_dates_without_last_modified() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(
+                http::header::IF_RANGE.as_str(),
+                "Sun, 06 Nov 1994 08:49:37 GMT",
+            )
+            .body(())
+            .build();
+        assert!(!req.if_range_satisfied("\"abc\"", None));
+    }
+
+    #[test]
+    fn preferred_charset_should_respect_quality_values() {
+        let test_data = vec![
+            (None, vec!["utf-8"], Some("utf-8")),
+            (
+                Some("utf-8;q=0.8, iso-8859-1;q=0.9"),
+                vec!["utf-8"],
+                Some("utf-8"),
+            ),
+            (
+                Some("utf-8;q=0.8, iso-8859-1;q=0.9"),
+                vec!["utf-8", "ISO-8859-1"],
+                Some("ISO-8859-1"),
+            ),
+            (Some("iso-8859-1, *;q=0.1"), vec!["utf-8"], Some("utf-8")),
+            (Some("iso-8859-1"), vec!["utf-8"], None),
+            (Some("utf-8;q=0"), vec!["utf-8"], None),
+            (Some("utf-8;q=0, *"), vec!["utf-8"], None),
+            (
+                Some("utf-8;q=0, *"),
+                vec!["utf-8", "iso-8859-1"],
+                Some("iso-8859-1"),
+            ),
+            (
+                Some("*, utf-8;q=0.5"),
+                vec!["utf-8", "iso-8859-1"],
+                Some("iso-8859-1"),
+            ),
+        ];
+        for (accept_charset, supported, expected) in test_data {
+            let mut builder = RequestBuilder::new(Method::Get, "http://foo.bar");
+            if let Some(accept_charset) = accept_charset {
+                builder.header(http::header::ACCEPT_CHARSET.as_str(), accept_charset);
+            }
+            let req = builder.body(()).build();
+            assert_eq!(
+                req.preferred_charset(&supported).as_deref(),
+                expected,
+                "Accept-Charset: {:?}",
+                accept_charset
+            );
+        }
+    }
+
+    #[test]
+    fn body_bytes_limited_should_read_bodies_within_the_limit() {
+        let chunks: Vec<std::result::Result<Vec<u8>, ()>> = vec![Ok(vec![1, 2]), Ok(vec![3])];
+        let sut = futures::executor::block_on(body_bytes_limited(futures::stream::iter(chunks), 3));
+        assert_eq!(sut, Ok(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn body_bytes_limited_should_abort_early_if_limit_is_exceeded() {
+        use futures::StreamExt;
+
+        let pulled = std::cell::Cell::new(0);
+        let stream = futures::stream::iter(0..100).map(|_| {
+            pulled.set(pulled.get() + 1);
+            Ok::<Vec<u8>, ()>(vec![0; 10])
+        });
+        let sut = futures::executor::block_on(body_bytes_limited(stream, 25));
+
+        assert_eq!(sut, Err(ContribError::PayloadTooLarge(25)));
+        assert_eq!(pulled.get(), 3);
+    }
+
+    #[test]
+    fn body_bytes_limited_should_surface_stream_errors() {
+        let chunks = vec![Ok(vec![1]), Err("connection reset")];
+        let sut =
+            futures::executor::block_on(body_bytes_limited(futures::stream::iter(chunks), 1024));
+        assert!(matches!(sut, Err(ContribError::Stream(_))));
+    }
+
+    #[test]
+    fn has_cookie_and_cookie_count_should_inspect_cookie_header() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(http::header::COOKIE.as_str(), "session=abc; theme=dark")
+            .body(())
+            .build();
+        assert!(req.has_cookie("session"));
+        assert!(req.has_cookie("theme"));
+        assert!(!req.has_cookie("Session"));
+        assert_eq!(req.cookie_count(), 2);
+
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .body(())
+            .build();
+        assert!(!req.has_cookie("session"));
+        assert_eq!(req.cookie_count(), 0);
+    }
+
+    #[test]
+    fn get_path_and_get_path_and_query_should_decode_path() {
+        let test_data = vec![
+            (
+                "http://foo.bar/api/items?page=2",
+                Some("/items"),
+                "/items",
+                "/items?page=2",
+            ),
+            (
+                "http://foo.bar/api/hello%20world?q=a%20b",
+                None,
+                "/api/hello world",
+                "/api/hello world?q=a%20b",
+            ),
+            ("http://foo.bar/api?", Some(""), "/", "/"),
+            ("http://foo.bar/?page=1", Some("/"), "/", "/?page=1"),
+            ("http://foo.bar", None, "/", "/"),
+        ];
+        for (uri, path_info, expected_path, expected_path_and_query) in test_data {
+            let mut builder = RequestBuilder::new(Method::Get, uri);
+            if let Some(path_info) = path_info {
+                builder.header(HEADER_SPIN_PATH_INFO, path_info);
+            }
+            let req = builder.body(()).build();
+            assert_eq!(req.get_path(), expected_path, "{}", uri);
+            assert_eq!(req.get_path_and_query(), expected_path_and_query, "{}", uri);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_body_guarded_should_check_content_type_size_and_syntax() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Todo {
+            title: String,
+        }
+
+        let test_data = vec![
+            ("application/json", r#"{"title":"Spin"}"#, None),
+            ("application/merge-patch+json", r#"{"title":"Spin"}"#, None),
+            ("text/plain", r#"{"title":"Spin"}"#, Some(415)),
+            ("", r#"{"title":"Spin"}"#, Some(415)),
+            (
+                "application/json",
+                r#"{"title":"Spin is great"}"#,
+                Some(413),
+            ),
+            ("application/json", r#"{"title":}"#, Some(400)),
+            ("application/json", r#"{"name":"x"}"#, Some(400)),
+        ];
+        for (content_type, body, expected) in test_data {
+            let req = RequestBuilder::new(Method::Post, "http://foo.bar")
+                .header(http::header::CONTENT_TYPE.as_str(), content_type)
+                .body(body)
+                .build();
+            let sut = req.json_body_guarded::<Todo>(20);
+            match expected {
+                None => assert_eq!(
+                    sut.expect("Body not deserialized"),
+                    Todo {
+                        title: "Spin".to_string()
+                    }
+                ),
+                Some(status) => assert_eq!(
+                    sut.expect_err("Body deserialized").status(),
+                    &status,
+                    "{}: {}",
+                    content_type,
+                    body
+                ),
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_body_guarded_should_reject_declared_content_length_exceeding_limit() {
+        let req = RequestBuilder::new(Method::Post, "http://foo.bar")
+            .header(http::header::CONTENT_TYPE.as_str(), "application/json")
+            .header(http::header::CONTENT_LENGTH.as_str(), "4096")
+            .body("{}")
+            .build();
+        let sut = req.json_body_guarded::<serde_json::Value>(1024);
+        assert_eq!(sut.expect_err("Body deserialized").status(), &413);
+    }
+
+    #[test]
+    fn get_cookie_as_bool_should_parse_common_representations() {
+        let test_data = vec![
+            ("pref=1", Some(true)),
+            ("pref=true", Some(true)),
+            ("pref=YES", Some(true)),
+            ("pref=0", Some(false)),
+            ("pref=no", Some(false)),
+            ("pref=maybe", None),
+            ("other=1", None),
+        ];
+        for (cookie, expected) in test_data {
+            let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+                .header(http::header::COOKIE.as_str(), cookie)
+                .body(())
+                .build();
+            assert_eq!(req.get_cookie_as_bool("pref"), expected, "{}", cookie);
+        }
+    }
+
+    #[test]
+    fn get_cookie_parsed_should_parse_values_using_from_str() {
+        let req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header(
+                http::header::COOKIE.as_str(),
+                "page_size=50; ratio=0.5; name=x",
+            )
+            .body(())
+            .build();
+        assert_eq!(req.get_cookie_parsed::<u32>("page_size"), Some(50));
+        assert_eq!(req.get_cookie_parsed::<f64>("ratio"), Some(0.5));
+        assert_eq!(req.get_cookie_parsed::<u32>("name"), None);
+        assert_eq!(req.get_cookie_parsed::<u32>("missing"), None);
+    }
+
+    #[test]
+    fn get_header_ci_should_ignore_casing_of_names() {
+        let mut req = RequestBuilder::new(Method::Get, "http://foo.bar")
+            .header("x-request-source", "builder")
+            .body(())
+            .build();
+        req.set_header("X-Api-Key", "secret");
+
+        for name in ["X-Api-Key", "x-api-key", "X-API-KEY"] {
+            assert_eq!(
+                req.get_header_ci(name).as_deref(),
+                Some("secret"),
+                "{}",
+                name
+            );
+        }
+        for name in ["X-Request-Source", "x-request-source"] {
+            assert_eq!(
+                req.get_header_ci(name).as_deref(),
+                Some("builder"),
+                "{}",
+                name
+            );
+        }
+        assert!(req.get_header_ci("x-missing").is_none());
+    }
+
+    #[test]
+    fn preflight_details_should_only_be_returned_for_preflights() {
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD.as_str(), "post")
+            .header(
+                http::header::ACCESS_CONTROL_REQUEST_HEADERS.as_str(),
+                "Content-Type, x-custom,",
+            )
+            .body(())
+            .build();
+        let sut = req.preflight_details().expect("Preflight details missing");
+        assert_eq!(
+            sut,
+            PreflightRequest {
+                origin: "http://localhost:4200".to_string(),
+                requested_method: "POST".to_string(),
+                requested_headers: vec!["content-type".to_string(), "x-custom".to_string()],
+            }
+        );
+
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+            .body(())
+            .build();
+        assert!(req.preflight_details().is_none());
+
+        let req = RequestBuilder::new(Method::Options, "http://foo.bar")
+            .header(http::header::ORIGIN.as_str(), "http://localhost:4200")
+            .body(())
+            .build();
+        assert!(req.preflight_details().is_none());
+    }
+
+    #[test]
+    fn accepts_encoding_should_honor_quality_values() {
+        let test_data = vec![
+            (None, "gzip", true),
+            (Some("gzip;q=0, br"), "gzip", false),
+            (Some("gzip;q=0, br"), "br", true),
+            (Some("gzip;q=0, br"), "identity", true),
+            (Some("GZIP"), "gzip", true),
+            (Some("br"), "gzip", false),
+            (Some("*"), "gzip", true),
+            (Some("*;q=0, br"), "gzip", false),
+            (Some("*;q=0, br"), "identity", false),
+            (Some("gzip, *;q=0"), "gzip", true),
+            (Some("identity;q=0, gzip"), "identity", false),
+            (Some(""), "gzip", false),
+            (Some(""), "identity", true),
+        ];
+        for (accept_encoding, coding, expected) in test_data {
+            let mut builder = RequestBuilder::new(Method::Get, "http://foo.bar");
+            if let Some(accept_encoding) = accept_encoding {
+                builder.header(http::header::ACCEPT_ENCODING.as_str(), accept_encoding);
+            }
+            let req = builder.body(()).build();
+            assert_eq!(
+                req.accepts_encoding(coding),
+                expected,
+                "Accept-Encoding: {:?}, coding: {}",
+                accept_encoding,
+                coding
+            );
         }
     }
 }
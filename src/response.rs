@@ -1,6 +1,553 @@
+use std::fmt::Display;
+use std::time::SystemTime;
+
+#[cfg(feature = "anyhow")]
 use anyhow::Result;
 
-use spin_sdk::http::Response;
+#[cfg(feature = "anyhow")]
+use spin_sdk::http::Method;
+#[cfg(any(feature = "anyhow", feature = "compression"))]
+use spin_sdk::http::Request;
+use spin_sdk::http::{HeaderValue, IntoResponse, Response, ResponseBuilder};
+
+use crate::error::ContribError;
+use crate::httpdate::format_http_date;
+use crate::mediatype::MediaType;
+#[cfg(feature = "compression")]
+use crate::request::Contrib;
+use crate::request::HEADER_X_REQUEST_ID;
+#[cfg(feature = "anyhow")]
+use crate::sse::SseEvent;
+
+/// Variants of the response helpers returning `Result<Response, ContribError>`
+///
+/// These don't depend on `anyhow` and are available regardless of the `anyhow` feature.
+/// `ContribError` implements `IntoResponse`, so the results can be returned from Spin handlers directly
+pub mod typed;
+
+const HEADER_SERVER_TIMING: &str = "server-timing";
+#[cfg(feature = "json")]
+const HEADER_X_CONTENT_TYPE_OPTIONS: &str = "x-content-type-options";
+/// Number of hex characters of the SHA-1 digest used for ETags (128 bits)
+#[cfg(feature = "etag")]
+const ETAG_HEX_LENGTH: usize = 32;
+
+/// Name of the cookie used by `redirect_with_flash` to carry the flash message
+pub const FLASH_COOKIE_NAME: &str = "flash";
+/// Number of seconds a flash cookie remains valid
+const FLASH_COOKIE_MAX_AGE: u64 = 60;
+
+/// Wrapper around the results of the response helpers, which implements `spin_sdk::http::IntoResponse`
+///
+/// This allows returning helper results directly from `#[http_component]` functions and `Router` handlers.
+/// An `anyhow::Error` is turned into a `500 Internal Server Error`, a `ContribError` into the response
+/// matching its status code (see `ContribError::status_code`)
+///
+/// # Example
+/// ```rust
+/// use spin_sdk::http::{Params, Request, Router};
+/// use spin_contrib_http::response::{typed::not_found, ContribResponse};
+///
+/// fn handler(_req: Request, _params: Params) -> ContribResponse {
+///   not_found().into()
+/// }
+///
+/// let mut router = Router::default();
+/// router.get("/", handler);
+/// ```
+pub struct ContribResponse(Response);
+
+#[cfg(feature = "anyhow")]
+impl From<Result<Response>> for ContribResponse {
+    fn from(value: Result<Response>) -> Self {
+        ContribResponse(value.into_response())
+    }
+}
+
+impl From<std::result::Result<Response, ContribError>> for ContribResponse {
+    fn from(value: std::result::Result<Response, ContribError>) -> Self {
+        ContribResponse(value.into_response())
+    }
+}
+
+impl From<Response> for ContribResponse {
+    fn from(value: Response) -> Self {
+        ContribResponse(value)
+    }
+}
+
+impl IntoResponse for ContribResponse {
+    fn into_response(self) -> Response {
+        self.0
+    }
+}
+
+/// Extensions for spin_sdk::http::ResponseBuilder
+pub trait ContribResponseBuilder {
+    /// Merges the provided fields into the `Vary` header of the response.
+    ///
+    /// Fields already present in the `Vary` header are not added again (compared case-insensitively)
+    ///
+    /// # Arguments
+    ///
+    /// * `fields` - The names of the request headers the response varies on
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::ResponseBuilder;
+    /// use spin_contrib_http::response::ContribResponseBuilder;
+    ///
+    /// let response = ResponseBuilder::new(200)
+    ///     .header("vary", "Accept-Encoding")
+    ///     .with_vary(&["Accept", "accept-encoding"])
+    ///     .build();
+    ///
+    /// let vary = response.header("vary").unwrap().as_str().unwrap();
+    /// assert_eq!(vary, "Accept-Encoding, Accept");
+    /// ```
+    fn with_vary(&mut self, fields: &[&str]) -> &mut Self;
+
+    /// Sets the `X-Request-Id` header to correlate the response with its request
+    ///
+    /// # Arguments
+    ///
+    /// * `request_id` - The correlation id (e.g. obtained from `request::Contrib::request_id`)
+    fn with_request_id(&mut self, request_id: &str) -> &mut Self;
+
+    /// Sets the `Server-Timing` header based on the recorded metrics
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::ResponseBuilder;
+    /// use spin_contrib_http::response::{ContribResponseBuilder, ServerTiming};
+    ///
+    /// let mut timing = ServerTiming::new();
+    /// timing.add("db", Some(53.2), Some("Database"));
+    /// timing.add("miss", None, None);
+    ///
+    /// let response = ResponseBuilder::new(200)
+    ///     .with_server_timing(&timing)
+    ///     .build();
+    ///
+    /// let header = response.header("server-timing").unwrap().as_str().unwrap();
+    /// assert_eq!(header, "db;dur=53.2;desc=\"Database\", miss");
+    /// ```
+    fn with_server_timing(&mut self, timing: &ServerTiming) -> &mut Self;
+
+    /// Sets the `Expires` header to the provided point in time (formatted as HTTP-date)
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    /// use spin_sdk::http::ResponseBuilder;
+    /// use spin_contrib_http::response::ContribResponseBuilder;
+    ///
+    /// let response = ResponseBuilder::new(200)
+    ///     .with_expires(UNIX_EPOCH + Duration::from_secs(784_111_777))
+    ///     .build();
+    ///
+    /// let expires = response.header("expires").unwrap().as_str().unwrap();
+    /// assert_eq!(expires, "Sun, 06 Nov 1994 08:49:37 GMT");
+    /// ```
+    fn with_expires(&mut self, expires: SystemTime) -> &mut Self;
+
+    /// Sets the `Age` header to the number of seconds the response has been cached
+    fn with_age(&mut self, secs: u64) -> &mut Self;
+
+    /// Sets the `Location` header to the provided URL
+    ///
+    /// URLs containing control characters (such as CR or LF) are rejected with `ContribError::InvalidHeader`,
+    /// which prevents HTTP response splitting
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::ResponseBuilder;
+    /// use spin_contrib_http::response::ContribResponseBuilder;
+    ///
+    /// let response = ResponseBuilder::new(201)
+    ///     .with_location("/users/42")
+    ///     .unwrap()
+    ///     .build();
+    ///
+    /// assert_eq!(response.header("location").unwrap().as_str(), Some("/users/42"));
+    /// assert!(ResponseBuilder::new(302).with_location("/\r\nSet-Cookie: a=b").is_err());
+    /// ```
+    fn with_location(&mut self, url: &str) -> std::result::Result<&mut Self, ContribError>;
+
+    /// Prevents caching of the response by setting `Cache-Control: no-store` and `Pragma: no-cache`
+    ///
+    /// Use this for authentication and other user-specific responses (see `no_cache_response`)
+    fn with_no_store(&mut self) -> &mut Self;
+
+    /// Sets the `ETag` header to a strong ETag computed from the current body (see `etag_for`)
+    ///
+    /// Requires the `etag` feature
+    #[cfg(feature = "etag")]
+    fn with_etag_from_body(&mut self) -> &mut Self;
+}
+
+/// Computes a strong ETag (quoted hex of the truncated SHA-1 digest) for the provided bytes
+///
+/// Requires the `etag` feature
+///
+/// # Example
+/// ```rust
+/// use spin_contrib_http::response::etag_for;
+///
+/// let etag = etag_for(b"Hello, Spin");
+/// assert!(etag.starts_with('"') && etag.ends_with('"'));
+/// assert_eq!(etag, etag_for(b"Hello, Spin"));
+/// ```
+#[cfg(feature = "etag")]
+pub fn etag_for(bytes: &[u8]) -> String {
+    let digest = sha1_smol::Sha1::from(bytes).digest().to_string();
+    format!("\"{}\"", &digest[..ETAG_HEX_LENGTH])
+}
+
+/// Collects metrics for the `Server-Timing` response header
+#[derive(Debug, Clone, Default)]
+pub struct ServerTiming {
+    metrics: Vec<ServerTimingMetric>,
+}
+
+#[derive(Debug, Clone)]
+struct ServerTimingMetric {
+    name: String,
+    duration_ms: Option<f64>,
+    description: Option<String>,
+}
+
+impl ServerTiming {
+    /// Creates an empty set of metrics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a metric
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the metric
+    /// * `duration_ms` - The duration of the metric in milliseconds (optional)
+    /// * `description` - A human-readable description of the metric (optional)
+    pub fn add(
+        &mut self,
+        name: &str,
+        duration_ms: Option<f64>,
+        description: Option<&str>,
+    ) -> &mut Self {
+        self.metrics.push(ServerTimingMetric {
+            name: name.to_string(),
+            duration_ms,
+            description: description.map(|d| d.to_string()),
+        });
+        self
+    }
+
+    /// Determines if no metrics have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+}
+
+impl Display for ServerTiming {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, metric) in self.metrics.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", metric.name)?;
+            if let Some(duration_ms) = metric.duration_ms {
+                write!(f, ";dur={}", duration_ms)?;
+            }
+            if let Some(description) = &metric.description {
+                write!(f, ";desc={}", quoted_string(description))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ContribResponseBuilder for ResponseBuilder {
+    fn with_vary(&mut self, fields: &[&str]) -> &mut Self {
+        let mut response = self.build();
+        let existing = response
+            .header(http::header::VARY.as_str())
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let merged = merge_vary(&existing, fields);
+        if !merged.is_empty() {
+            response.set_header(http::header::VARY.as_str(), merged);
+        }
+        *self = response.into_builder();
+        self
+    }
+
+    fn with_request_id(&mut self, request_id: &str) -> &mut Self {
+        self.header(HEADER_X_REQUEST_ID, request_id)
+    }
+
+    fn with_server_timing(&mut self, timing: &ServerTiming) -> &mut Self {
+        if timing.is_empty() {
+            return self;
+        }
+        self.header(HEADER_SERVER_TIMING, timing.to_string())
+    }
+
+    fn with_expires(&mut self, expires: SystemTime) -> &mut Self {
+        self.header(http::header::EXPIRES.as_str(), format_http_date(expires))
+    }
+
+    fn with_age(&mut self, secs: u64) -> &mut Self {
+        self.header(http::header::AGE.as_str(), secs.to_string())
+    }
+
+    fn with_location(&mut self, url: &str) -> std::result::Result<&mut Self, ContribError> {
+        if url.chars().any(|c| c.is_control()) {
+            return Err(ContribError::InvalidHeader(format!(
+                "location must not contain control characters: {:?}",
+                url
+            )));
+        }
+        Ok(self.header(http::header::LOCATION.as_str(), url))
+    }
+
+    fn with_no_store(&mut self) -> &mut Self {
+        self.header(http::header::CACHE_CONTROL.as_str(), "no-store")
+            .header(http::header::PRAGMA.as_str(), "no-cache")
+    }
+
+    #[cfg(feature = "etag")]
+    fn with_etag_from_body(&mut self) -> &mut Self {
+        let mut response = self.build();
+        let etag = etag_for(response.body());
+        response.set_header(http::header::ETAG.as_str(), etag);
+        *self = response.into_builder();
+        self
+    }
+}
+
+/// Headers which must occur at most once per response
+const SINGLE_VALUED_HEADERS: [&str; 4] = [
+    "content-type",
+    "content-length",
+    "location",
+    "access-control-allow-origin",
+];
+
+/// Extensions for spin_sdk::http::Response
+pub trait ResponseExt {
+    /// Removes duplicates of headers which must be singular (`Content-Type`, `Content-Length`, `Location`
+    /// and `Access-Control-Allow-Origin`).
+    ///
+    /// Spin stores one value per header name, but `Response::set_header` keeps the casing of the name,
+    /// so e.g. `Content-Type` and `content-type` can both be present. Spin doesn't record which of them was set last,
+    /// so for each of these headers one variant is kept by a fixed rule: the lowercase name (the value returned by
+    /// `Response::header`) if present, otherwise the lexicographically last name. Only the other case variants are
+    /// removed; all other headers (such as `Set-Cookie` or `Vary`) are left untouched
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::ResponseBuilder;
+    /// use spin_contrib_http::response::ResponseExt;
+    ///
+    /// let mut response = ResponseBuilder::new(200)
+    ///     .header("content-type", "application/json")
+    ///     .build();
+    /// response.set_header("Content-Type", "text/html");
+    ///
+    /// let response = response.dedupe_single_valued();
+    /// assert_eq!(response.headers().count(), 1);
+    /// assert_eq!(response.header("content-type").unwrap().as_str(), Some("application/json"));
+    /// ```
+    fn dedupe_single_valued(self) -> Response;
+
+    /// Decomposes the response into its parts (status and headers) and its body
+    ///
+    /// Use this in middleware to inspect or modify a response and re-assemble it using `ResponseParts::into_response`
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::ResponseBuilder;
+    /// use spin_contrib_http::response::ResponseExt;
+    ///
+    /// let response = ResponseBuilder::new(200).body("Hello, Spin").build();
+    ///
+    /// let (mut parts, body) = response.into_parts();
+    /// parts.status = 201;
+    /// parts.set_header("x-frame-options", "DENY");
+    /// let response = parts.into_response(body);
+    ///
+    /// assert_eq!(response.status(), &201);
+    /// assert_eq!(response.header("x-frame-options").unwrap().as_str(), Some("DENY"));
+    /// assert_eq!(response.body(), b"Hello, Spin");
+    /// ```
+    fn into_parts(self) -> (ResponseParts, Vec<u8>);
+
+    /// Determines if the status code indicates success (`2xx`)
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::Response;
+    /// use spin_contrib_http::response::ResponseExt;
+    ///
+    /// let response = Response::new(404, ());
+    /// assert!(response.is_client_error());
+    /// assert!(!response.is_success());
+    /// ```
+    fn is_success(&self) -> bool;
+
+    /// Determines if the status code indicates a redirection (`3xx`)
+    fn is_redirect(&self) -> bool;
+
+    /// Determines if the status code indicates a client error (`4xx`)
+    fn is_client_error(&self) -> bool;
+
+    /// Determines if the status code indicates a server error (`5xx`)
+    fn is_server_error(&self) -> bool;
+}
+
+/// The status and headers of a decomposed response (see `ResponseExt::into_parts`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseParts {
+    /// The status code
+    pub status: u16,
+    /// The headers (names as stored in the response)
+    pub headers: Vec<(String, HeaderValue)>,
+}
+
+impl ResponseParts {
+    /// Returns the value of the header with the provided name (compared case-insensitively)
+    pub fn header(&self, name: &str) -> Option<&HeaderValue> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Sets the header with the provided name, replacing all existing values (compared case-insensitively)
+    pub fn set_header(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        self.headers
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(&name));
+        self.headers.push((name, HeaderValue::string(value.into())));
+    }
+
+    /// Re-assembles a response from the parts and the provided body
+    ///
+    /// Header names are lowercased (like `ResponseBuilder::header` does). If a name occurs multiple times,
+    /// the last value wins
+    pub fn into_response(self, body: Vec<u8>) -> Response {
+        let headers = self
+            .headers
+            .into_iter()
+            .map(|(name, value)| (name, value.into_bytes()))
+            .collect::<Vec<(String, Vec<u8>)>>();
+        Response::builder()
+            .status(self.status)
+            .headers(headers)
+            .body(body)
+            .build()
+    }
+}
+
+impl ResponseExt for Response {
+    fn dedupe_single_valued(self) -> Response {
+        let mut removed: Vec<String> = vec![];
+        for single_valued in SINGLE_VALUED_HEADERS {
+            let variants: Vec<&str> = self
+                .headers()
+                .map(|(name, _)| name)
+                .filter(|name| name.eq_ignore_ascii_case(single_valued))
+                .collect();
+            if variants.len() < 2 {
+                continue;
+            }
+            let kept = match variants.contains(&single_valued) {
+                true => single_valued,
+                false => variants.iter().copied().max().unwrap_or_default(),
+            };
+            removed.extend(
+                variants
+                    .into_iter()
+                    .filter(|name| *name != kept)
+                    .map(|name| name.to_string()),
+            );
+        }
+        if removed.is_empty() {
+            return self;
+        }
+
+        // Spin can't remove a single header, so the response is rebuilt without the removed names.
+        // The builder lowercases names, so names containing uppercase characters are re-added using
+        // set_header afterwards. Those can only be set using set_header, hence they always hold strings
+        let mut headers: Vec<(String, Vec<u8>)> = vec![];
+        let mut cased: Vec<(String, String)> = vec![];
+        for (name, value) in self.headers() {
+            if removed.iter().any(|removed| removed == name) {
+                continue;
+            }
+            if name.chars().any(|c| c.is_ascii_uppercase()) {
+                cased.push((name.to_string(), value.clone().into_utf8_lossy()));
+            } else {
+                headers.push((name.to_string(), value.as_bytes().to_vec()));
+            }
+        }
+
+        let mut response = self.into_builder().headers(headers).build();
+        for (name, value) in cased {
+            response.set_header(name, value);
+        }
+        response
+    }
+
+    fn into_parts(self) -> (ResponseParts, Vec<u8>) {
+        let parts = ResponseParts {
+            status: *self.status(),
+            headers: self
+                .headers()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+        };
+        (parts, self.into_body())
+    }
+
+    fn is_success(&self) -> bool {
+        (200..300).contains(self.status())
+    }
+
+    fn is_redirect(&self) -> bool {
+        (300..400).contains(self.status())
+    }
+
+    fn is_client_error(&self) -> bool {
+        (400..500).contains(self.status())
+    }
+
+    fn is_server_error(&self) -> bool {
+        (500..600).contains(self.status())
+    }
+}
+
+pub(crate) fn merge_vary(existing: &str, fields: &[&str]) -> String {
+    let mut merged: Vec<&str> = vec![];
+    for field in existing.split(',').chain(fields.iter().copied()) {
+        let field = field.trim();
+        if field.is_empty()
+            || merged
+                .iter()
+                .any(|present| present.eq_ignore_ascii_case(field))
+        {
+            continue;
+        }
+        merged.push(field);
+    }
+    if merged.contains(&"*") {
+        return "*".to_string();
+    }
+    merged.join(", ")
+}
 
 /// Returns a `Result<spin_sdk::http::Response>` representing a redirect to the provided URL
 /// with the provided status code and Location header
@@ -24,19 +571,18 @@ use spin_sdk::http::Response;
 ///   redirect(target, permanent)
 /// }
 /// ```
+#[cfg(feature = "anyhow")]
 pub fn redirect(url: &str, permanent: bool) -> Result<Response> {
-    let mut status_code = http::StatusCode::TEMPORARY_REDIRECT;
-    if permanent {
-        status_code = http::StatusCode::PERMANENT_REDIRECT;
-    }
-    Ok(Response::builder()
-        .status(status_code)
-        .header(http::header::LOCATION.as_str(), url)
-        .body(())
-        .build())
+    Ok(typed::redirect(url, permanent)?)
 }
 
-/// Returns a `Result<spin_sdk::http::Response>` representing a 400 Bad Request
+/// Returns a `Result<spin_sdk::http::Response>` representing a 301 Moved Permanently to the provided URL
+///
+/// The method may change to `GET` when the redirect is followed
+///
+/// # Arguments
+///
+/// * `url` - The URL to redirect to (set as `Location` header)
 ///
 /// # Example
 /// ```rust
@@ -44,105 +590,1993 @@ pub fn redirect(url: &str, permanent: bool) -> Result<Response> {
 /// use spin_sdk::{
 ///  http::{Request, Response},
 /// };
-/// use spin_contrib_http::response::bad_request;  
+/// use spin_contrib_http::response::moved_permanently;
 ///
 /// pub fn handler(req: Request) -> Result<Response> {
-///   bad_request()
+///   moved_permanently("https://example.com")
 /// }
 /// ```
-pub fn bad_request() -> Result<Response> {
-    create_response(http::StatusCode::BAD_REQUEST)
+#[cfg(feature = "anyhow")]
+pub fn moved_permanently(url: &str) -> Result<Response> {
+    Ok(typed::moved_permanently(url)?)
 }
 
-/// Returns a `Result<spin_sdk::http::Response>` representing a 204 No Content
+/// Returns a `Result<spin_sdk::http::Response>` representing a 302 Found to the provided URL
+///
+/// The method may change to `GET` when the redirect is followed
+///
+/// # Arguments
+///
+/// * `url` - The URL to redirect to (set as `Location` header)
 ///
 /// # Example
 /// ```rust
 /// use anyhow::Result;
 /// use spin_sdk::{
 ///  http::{Request, Response},
-/// };  
-/// use spin_contrib_http::response::no_content;
+/// };
+/// use spin_contrib_http::response::found;
 ///
 /// pub fn handler(req: Request) -> Result<Response> {
-///  no_content()
+///   found("https://example.com")
 /// }
 /// ```
-pub fn no_content() -> Result<Response> {
-    create_response(http::StatusCode::NO_CONTENT)
+#[cfg(feature = "anyhow")]
+pub fn found(url: &str) -> Result<Response> {
+    Ok(typed::found(url)?)
 }
 
-/// Returns a `Result<spin_sdk::http::Response>` with desired status code
+/// Returns a `Result<spin_sdk::http::Response>` representing a 303 See Other to the provided URL
+///
+/// The redirect is followed using `GET` (e.g. after submitting a form)
 ///
 /// # Arguments
 ///
-/// * `code` - The desired status code
+/// * `url` - The URL to redirect to (set as `Location` header)
 ///
 /// # Example
 /// ```rust
 /// use anyhow::Result;
 /// use spin_sdk::{
 ///  http::{Request, Response},
-/// };  
-/// use spin_contrib_http::response::status_code;
+/// };
+/// use spin_contrib_http::response::see_other;
 ///
 /// pub fn handler(req: Request) -> Result<Response> {
-///   status_code(http::StatusCode::OK)
+///   see_other("https://example.com")
 /// }
 /// ```
-pub fn status_code(status_code: http::StatusCode) -> Result<Response> {
-    create_response(status_code)
+#[cfg(feature = "anyhow")]
+pub fn see_other(url: &str) -> Result<Response> {
+    Ok(typed::see_other(url)?)
 }
 
-fn create_response(status_code: http::StatusCode) -> Result<Response> {
-    Ok(Response::new(status_code, ()))
+/// Returns a `Result<spin_sdk::http::Response>` representing a 307 Temporary Redirect to the provided URL
+///
+/// The method and body are preserved when the redirect is followed
+///
+/// # Arguments
+///
+/// * `url` - The URL to redirect to (set as `Location` header)
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::temporary_redirect;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   temporary_redirect("https://example.com")
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn temporary_redirect(url: &str) -> Result<Response> {
+    Ok(typed::temporary_redirect(url)?)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn no_content_should_set_status_code_to_204() {
-        let sut = no_content().unwrap();
-        assert_eq!(sut.status(), &http::StatusCode::NO_CONTENT.as_u16());
-    }
+/// Returns a `Result<spin_sdk::http::Response>` representing a 308 Permanent Redirect to the provided URL
+///
+/// The method and body are preserved when the redirect is followed
+///
+/// # Arguments
+///
+/// * `url` - The URL to redirect to (set as `Location` header)
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::permanent_redirect;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   permanent_redirect("https://example.com")
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn permanent_redirect(url: &str) -> Result<Response> {
+    Ok(typed::permanent_redirect(url)?)
+}
 
-    #[test]
-    fn bad_request_should_set_status_code_to_400() {
-        let sut = bad_request().unwrap();
-        assert_eq!(sut.status(), &http::StatusCode::BAD_REQUEST.as_u16());
-    }
+/// Returns a `301 Moved Permanently` redirect to the `https://` equivalent of the request URL, if the request was sent using HTTP
+///
+/// The scheme is determined using `request::Contrib::scheme`. If the request was sent using HTTPS, `None` is returned.
+/// The host is taken from `Host` (or `X-Forwarded-Host`), falling back to the request URI. An explicit port is
+/// dropped, because the HTTP port can't serve HTTPS. If no host can be determined, a 400 Bad Request is returned
+///
+/// # Arguments
+///
+/// * `req` - The incoming request
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::{enforce_https, ok};
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   if let Some(redirect) = enforce_https(&req) {
+///     return redirect;
+///   }
+///   ok("Hello, Spin", Some("text/plain"))
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn enforce_https(req: &Request) -> Option<Result<Response>> {
+    typed::enforce_https(req).map(|response| response.map_err(Into::into))
+}
 
-    #[test]
-    fn redirect_should_set_status_code_307_for_temporary() {
-        let sut = redirect("http://localhost:3000", false).unwrap();
-        assert_eq!(sut.status(), &http::StatusCode::TEMPORARY_REDIRECT.as_u16());
+/// Removes an explicit port from the provided host (IPv6 literals are kept intact)
+fn strip_port(host: &str) -> &str {
+    match host.rsplit_once(':') {
+        Some((name, port))
+            if !port.is_empty()
+                && port.chars().all(|c| c.is_ascii_digit())
+                && (!name.contains(':') || name.ends_with(']')) =>
+        {
+            name
+        }
+        _ => host,
     }
+}
 
-    #[test]
-    fn redirect_should_set_status_code_302_for_permanent() {
-        let sut = redirect("http://localhost:3000", true).unwrap();
-        assert_eq!(sut.status(), &http::StatusCode::PERMANENT_REDIRECT.as_u16());
-    }
+/// Returns a `Result<spin_sdk::http::Response>` representing a 303 See Other carrying a flash message
+///
+/// The flash message is percent-encoded and stored in a short-lived (60 seconds), `HttpOnly` cookie
+/// named `flash` (see `FLASH_COOKIE_NAME`), which is useful for the Post/Redirect/Get pattern
+///
+/// # Arguments
+///
+/// * `url` - The URL to redirect to
+/// * `flash` - The message to show after the redirect
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::redirect_with_flash;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   redirect_with_flash("/orders", "Order created")
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn redirect_with_flash(url: &str, flash: &str) -> Result<Response> {
+    Ok(typed::redirect_with_flash(url, flash)?)
+}
 
-    #[test]
-    fn redirect_should_set_location_header() {
-        let target = "http://localhost:3000";
-        let sut_permanent = redirect(target, true).unwrap();
-        let actual_permanent = sut_permanent
-            .header(http::header::LOCATION.as_str())
-            .expect("Header LOCATION not present")
-            .as_str()
-            .expect("Could not convert value to str");
-        assert_eq!(actual_permanent, target);
+/// Returns a `Result<spin_sdk::http::Response>` representing a 303 See Other which removes a cookie
+///
+/// The removal cookie is scoped to `Path=/` (see `Cookie::removal`). This implements the logout pattern:
+/// clear the session cookie and redirect to the login page
+///
+/// # Arguments
+///
+/// * `url` - The URL to redirect to
+/// * `cookie_name` - The name of the cookie to remove
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::redirect_clearing_cookie;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   redirect_clearing_cookie("/login", "session")
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn redirect_clearing_cookie(url: &str, cookie_name: &str) -> Result<Response> {
+    Ok(typed::redirect_clearing_cookie(url, cookie_name)?)
+}
 
-        let sut_temp = redirect(target, false).unwrap();
-        let actual_temp = sut_temp
-            .header(http::header::LOCATION.as_str())
+/// Returns a `Result<spin_sdk::http::Response>` representing a 200 OK with a CSV body (RFC 4180)
+///
+/// Rows are separated by CRLF. Fields containing commas, quotes or line breaks are quoted,
+/// and quotes inside fields are escaped by doubling them
+///
+/// # Arguments
+///
+/// * `rows` - The rows of the CSV document (including a header row, if desired)
+/// * `filename` - If provided, the response is marked as download using `Content-Disposition`
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::csv;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   let rows = vec![
+///     vec!["id".to_string(), "name".to_string()],
+///     vec!["1".to_string(), "Spin".to_string()],
+///   ];
+///   csv(rows, Some("export.csv"))
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn csv(
+    rows: impl IntoIterator<Item = Vec<String>>,
+    filename: Option<&str>,
+) -> Result<Response> {
+    Ok(typed::csv(rows, filename)?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 200 OK carrying Server-Sent Events
+///
+/// Sets `Content-Type: text/event-stream` and `Cache-Control: no-cache`. The events are sent at once.
+/// To push events over time, use `sse::send_events`, which streams the events to an `OutgoingResponse`
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::sse;
+/// use spin_contrib_http::sse::SseEvent;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   sse(vec![
+///     SseEvent::new("started").with_event("status"),
+///     SseEvent::new("{\"progress\":50}").with_id("1"),
+///   ])
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn sse(events: impl IntoIterator<Item = SseEvent>) -> Result<Response> {
+    Ok(typed::sse(events)?)
+}
+
+/// Formats a value as quoted-string (RFC 7230), escaping backslashes and quotes
+fn quoted_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        return format!("\"{}\"", field.replace('"', "\"\""));
+    }
+    field.to_string()
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 400 Bad Request
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::bad_request;  
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   bad_request()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn bad_request() -> Result<Response> {
+    Ok(typed::bad_request()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 204 No Content
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };  
+/// use spin_contrib_http::response::no_content;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///  no_content()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn no_content() -> Result<Response> {
+    Ok(typed::no_content()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 406 Not Acceptable
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::not_acceptable;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   not_acceptable()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn not_acceptable() -> Result<Response> {
+    Ok(typed::not_acceptable()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 415 Unsupported Media Type
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::unsupported_media_type;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   unsupported_media_type()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn unsupported_media_type() -> Result<Response> {
+    Ok(typed::unsupported_media_type()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 405 Method Not Allowed
+///
+/// The `Allow` header lists the provided methods, as required by HTTP
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Method, Request, Response},
+/// };
+/// use spin_contrib_http::response::method_not_allowed;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   method_not_allowed(&[Method::Get, Method::Post])
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn method_not_allowed(allowed: &[Method]) -> Result<Response> {
+    Ok(typed::method_not_allowed(allowed)?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 412 Precondition Failed
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::precondition_failed;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   precondition_failed()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn precondition_failed() -> Result<Response> {
+    Ok(typed::precondition_failed()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 413 Payload Too Large
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::payload_too_large;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   payload_too_large()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn payload_too_large() -> Result<Response> {
+    Ok(typed::payload_too_large()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 205 Reset Content
+///
+/// Like 204 No Content, the response carries neither a body nor a `Content-Type`
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::reset_content;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   reset_content()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn reset_content() -> Result<Response> {
+    Ok(typed::reset_content()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 502 Bad Gateway
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::bad_gateway;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   bad_gateway()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn bad_gateway() -> Result<Response> {
+    Ok(typed::bad_gateway()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 504 Gateway Timeout
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::gateway_timeout;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   gateway_timeout()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn gateway_timeout() -> Result<Response> {
+    Ok(typed::gateway_timeout()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 404 Not Found
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::not_found;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   not_found()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn not_found() -> Result<Response> {
+    Ok(typed::not_found()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 402 Payment Required
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::payment_required;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   payment_required()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn payment_required() -> Result<Response> {
+    Ok(typed::payment_required()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 411 Length Required
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::length_required;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   length_required()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn length_required() -> Result<Response> {
+    Ok(typed::length_required()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 500 Internal Server Error
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::internal_server_error;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   internal_server_error()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn internal_server_error() -> Result<Response> {
+    Ok(typed::internal_server_error()?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` with desired status code
+///
+/// # Arguments
+///
+/// * `code` - The desired status code
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };  
+/// use spin_contrib_http::response::status_code;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   status_code(http::StatusCode::OK)
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn status_code(status_code: http::StatusCode) -> Result<Response> {
+    Ok(typed::status_code(status_code)?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` with desired status code, body and (optional) content type
+///
+/// `Content-Length` is set to the size of the body, unless the status code must not carry a body
+/// (1xx, 204 No Content, 205 Reset Content and 304 Not Modified).
+/// Text-based content types (`text/*` and XML) without charset get `; charset=utf-8` appended
+///
+/// # Arguments
+///
+/// * `code` - The desired status code
+/// * `body` - The body of the response
+/// * `content_type` - The value of the `Content-Type` header (omitted if `None`)
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::status_with_body;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   status_with_body(http::StatusCode::CONFLICT, "Already exists", Some("text/plain"))
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn status_with_body(
+    code: http::StatusCode,
+    body: impl Into<bytes::Bytes>,
+    content_type: Option<&str>,
+) -> Result<Response> {
+    Ok(typed::status_with_body(code, body, content_type)?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 200 OK with the provided body
+///
+/// # Arguments
+///
+/// * `body` - The body of the response
+/// * `content_type` - The value of the `Content-Type` header (omitted if `None`)
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::ok;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   ok("Hello, Spin", Some("text/plain"))
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn ok(body: impl Into<bytes::Bytes>, content_type: Option<&str>) -> Result<Response> {
+    Ok(typed::ok(body, content_type)?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` with the provided XML body
+///
+/// The body is not validated, only `Content-Type` is set to `application/xml; charset=utf-8`
+///
+/// # Arguments
+///
+/// * `status` - The desired status code
+/// * `body` - The XML document
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::xml;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   xml(http::StatusCode::OK, "<greeting>Hello, Spin</greeting>")
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn xml(status: http::StatusCode, body: impl Into<bytes::Bytes>) -> Result<Response> {
+    Ok(typed::xml(status, body)?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` with the provided value serialized as JSON
+///
+/// `Content-Type` is set to `application/json` and `X-Content-Type-Options: nosniff` is added
+/// to prevent browsers from MIME sniffing the response. Use [`json_with_nosniff`] to opt out
+///
+/// Requires the `json` feature
+///
+/// # Arguments
+///
+/// * `status` - The desired status code
+/// * `value` - The value to serialize
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::json;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   json(http::StatusCode::OK, &vec!["Hello", "Spin"])
+/// }
+/// ```
+#[cfg(feature = "json")]
+#[cfg(feature = "anyhow")]
+pub fn json<T: serde::Serialize + ?Sized>(status: http::StatusCode, value: &T) -> Result<Response> {
+    Ok(typed::json(status, value)?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` with the provided value serialized as JSON
+///
+/// Same as [`json`], but allows controlling whether `X-Content-Type-Options: nosniff` is added
+///
+/// Requires the `json` feature
+///
+/// # Arguments
+///
+/// * `status` - The desired status code
+/// * `value` - The value to serialize
+/// * `nosniff` - Whether or not to add `X-Content-Type-Options: nosniff`
+#[cfg(feature = "json")]
+#[cfg(feature = "anyhow")]
+pub fn json_with_nosniff<T: serde::Serialize + ?Sized>(
+    status: http::StatusCode,
+    value: &T,
+    nosniff: bool,
+) -> Result<Response> {
+    Ok(typed::json_with_nosniff(status, value, nosniff)?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 422 Unprocessable Entity with structured validation errors
+///
+/// The body has the shape `{"errors":{"email":["is invalid"]}}` (fields are sorted by name).
+/// An empty map results in `{"errors":{}}`
+///
+/// Requires the `json` feature
+///
+/// # Arguments
+///
+/// * `errors` - The validation errors per field
+///
+/// # Example
+/// ```rust
+/// use std::collections::HashMap;
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::unprocessable_entity_json;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   let mut errors = HashMap::new();
+///   errors.insert("email".to_string(), vec!["is invalid".to_string()]);
+///   unprocessable_entity_json(&errors)
+/// }
+/// ```
+#[cfg(feature = "json")]
+#[cfg(feature = "anyhow")]
+pub fn unprocessable_entity_json(
+    errors: &std::collections::HashMap<String, Vec<String>>,
+) -> Result<Response> {
+    Ok(typed::unprocessable_entity_json(errors)?)
+}
+
+/// Compresses the body of the response using gzip, if the client accepts it
+///
+/// The response is compressed if `Accept-Encoding` of the request accepts `gzip` (or `*`), the body is not
+/// empty and the response has no `Content-Encoding` yet. `Content-Encoding` and `Content-Length` are updated accordingly.
+///
+/// `Accept-Encoding` is always merged into `Vary` (also if the response is passed through uncompressed),
+/// because the representation depends on the request header. This prevents caches from serving
+/// compressed responses to clients which can't decode them
+///
+/// Requires the `compression` feature
+///
+/// # Arguments
+///
+/// * `req` - The incoming request
+/// * `response` - The response to compress
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::{maybe_compress, ok};
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   let response = ok("Hello, Spin", Some("text/plain"))?;
+///   Ok(maybe_compress(&req, response))
+/// }
+/// ```
+#[cfg(feature = "compression")]
+pub fn maybe_compress(req: &Request, mut response: Response) -> Response {
+    use std::io::Write;
+
+    let existing_vary = response
+        .header(http::header::VARY.as_str())
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    response.set_header(
+        http::header::VARY.as_str(),
+        merge_vary(&existing_vary, &["Accept-Encoding"]),
+    );
+
+    // compress only if requested explicitly, although a missing header allows any coding
+    let accepts_gzip = req.header(http::header::ACCEPT_ENCODING.as_str()).is_some()
+        && req.accepts_encoding("gzip");
+    let status = http::StatusCode::from_u16(*response.status())
+        .unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+    if !accepts_gzip
+        || response.body().is_empty()
+        || !allows_body(status)
+        || response
+            .header(http::header::CONTENT_ENCODING.as_str())
+            .is_some()
+    {
+        return response;
+    }
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let compressed = match encoder
+        .write_all(response.body())
+        .and_then(|_| encoder.finish())
+    {
+        Ok(compressed) => compressed,
+        Err(_) => return response,
+    };
+    response.set_header(http::header::CONTENT_ENCODING.as_str(), "gzip");
+    response.set_header(
+        http::header::CONTENT_LENGTH.as_str(),
+        compressed.len().to_string(),
+    );
+    *response.body_mut() = compressed;
+    response
+}
+
+/// Prevents caching of the provided response by setting `Cache-Control: no-store` and `Pragma: no-cache`
+///
+/// Responses of login and token endpoints (or any other user-specific response) must not be cached.
+/// Existing `Cache-Control` and `Pragma` headers are replaced
+///
+/// # Arguments
+///
+/// * `response` - The response which must not be cached
+///
+/// # Example
+/// ```rust
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::error::ContribError;
+/// use spin_contrib_http::response::{no_cache_response, typed::ok};
+///
+/// pub fn handler(req: Request) -> Result<Response, ContribError> {
+///   let response = ok("token", Some("text/plain"))?;
+///   Ok(no_cache_response(response))
+/// }
+/// ```
+pub fn no_cache_response(response: Response) -> Response {
+    response.into_builder().with_no_store().build()
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 404 Not Found with a JSON body
+///
+/// The body has the shape `{"error":"not found"}`. Requires the `json` feature
+///
+/// # Arguments
+///
+/// * `message` - The error message (defaults to `not found`)
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::not_found_json;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   not_found_json(Some("user not found"))
+/// }
+/// ```
+#[cfg(feature = "json")]
+#[cfg(feature = "anyhow")]
+pub fn not_found_json(message: Option<&str>) -> Result<Response> {
+    Ok(typed::not_found_json(message)?)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 500 Internal Server Error with the provided body
+///
+/// If the content type denotes JSON (`application/json` or a `+json` suffix), the body is validated and
+/// `ContribError::Serialization` is returned if it isn't valid JSON. This catches mismatches between
+/// content type and body during development. Requires the `json` feature
+///
+/// # Arguments
+///
+/// * `body` - The body of the response
+/// * `content_type` - The value of the `Content-Type` header (omitted if `None`)
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::internal_server_error_checked;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   internal_server_error_checked(r#"{"error":"database unavailable"}"#, Some("application/json"))
+/// }
+/// ```
+#[cfg(feature = "json")]
+#[cfg(feature = "anyhow")]
+pub fn internal_server_error_checked(
+    body: impl Into<bytes::Bytes>,
+    content_type: Option<&str>,
+) -> Result<Response> {
+    Ok(typed::internal_server_error_checked(body, content_type)?)
+}
+
+/// Maps a failed deserialization to a 400 Bad Request response, which allows using `?` in handlers
+///
+/// The response carries the error message as `text/plain` body.
+/// Requires the `json` feature
+///
+/// # Arguments
+///
+/// * `res` - The result of the deserialization
+///
+/// # Example
+/// ```rust
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::{no_content, or_bad_request};
+///
+/// pub fn handler(req: Request) -> Result<Response, Response> {
+///   let names: Vec<String> = or_bad_request(serde_json::from_slice(req.body()))?;
+///   Ok(no_content().unwrap())
+/// }
+/// ```
+#[cfg(feature = "json")]
+pub fn or_bad_request<T>(res: serde_json::Result<T>) -> std::result::Result<T, Response> {
+    res.map_err(|e| deserialization_error_response(http::StatusCode::BAD_REQUEST, e))
+}
+
+/// Maps a failed deserialization to a 422 Unprocessable Entity response, which allows using `?` in handlers
+///
+/// Same as [`or_bad_request`], but uses 422. Requires the `json` feature
+///
+/// # Arguments
+///
+/// * `res` - The result of the deserialization
+#[cfg(feature = "json")]
+pub fn or_unprocessable_entity<T>(res: serde_json::Result<T>) -> std::result::Result<T, Response> {
+    res.map_err(|e| deserialization_error_response(http::StatusCode::UNPROCESSABLE_ENTITY, e))
+}
+
+#[cfg(feature = "json")]
+fn deserialization_error_response(status: http::StatusCode, error: serde_json::Error) -> Response {
+    let body = error.to_string();
+    Response::builder()
+        .status(status)
+        .header(
+            http::header::CONTENT_TYPE.as_str(),
+            "text/plain; charset=utf-8",
+        )
+        .header(
+            http::header::CONTENT_LENGTH.as_str(),
+            body.len().to_string(),
+        )
+        .body(body)
+        .build()
+}
+
+fn allows_body(code: http::StatusCode) -> bool {
+    !(code.is_informational()
+        || code == http::StatusCode::NO_CONTENT
+        || code == http::StatusCode::RESET_CONTENT
+        || code == http::StatusCode::NOT_MODIFIED)
+}
+
+/// Returns a `Result<spin_sdk::http::Response>` representing a 418 I'm a teapot
+///
+/// # Example
+/// ```rust
+/// use anyhow::Result;
+/// use spin_sdk::{
+///  http::{Request, Response},
+/// };
+/// use spin_contrib_http::response::im_a_teapot;
+///
+/// pub fn handler(req: Request) -> Result<Response> {
+///   im_a_teapot()
+/// }
+/// ```
+#[cfg(feature = "anyhow")]
+pub fn im_a_teapot() -> Result<Response> {
+    Ok(typed::im_a_teapot()?)
+}
+
+/// Appends `; charset=utf-8` to text-based content types (`text/*` and XML) without charset
+///
+/// Other content types (e.g. binary or JSON, which is always UTF-8) are returned as provided
+fn with_default_charset(content_type: &str) -> String {
+    let Some(media_type) = MediaType::parse(content_type) else {
+        return content_type.to_string();
+    };
+    let is_text = media_type.type_ == "text"
+        || media_type.subtype == "xml"
+        || media_type.subtype == "javascript"
+        || media_type.suffix() == Some("xml");
+    if !is_text || media_type.parameter("charset").is_some() {
+        return content_type.to_string();
+    }
+    format!(
+        "{}; charset=utf-8",
+        content_type.trim_end().trim_end_matches(';')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use spin_sdk::http::{Method, Params, Request, Router};
+
+    use super::*;
+    use crate::sse::SseEvent;
+
+    // the helpers are tested using the typed variants if the anyhow wrappers are not available
+    #[cfg(not(feature = "anyhow"))]
+    use super::typed::*;
+    #[cfg(not(feature = "anyhow"))]
+    type Result<T> = std::result::Result<T, ContribError>;
+
+    #[test]
+    fn no_content_should_set_status_code_to_204() {
+        let sut = no_content().unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::NO_CONTENT.as_u16());
+    }
+
+    #[test]
+    fn method_not_allowed_should_set_allow_header() {
+        let sut = method_not_allowed(&[Method::Get, Method::Post]).unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::METHOD_NOT_ALLOWED.as_u16());
+        let allow = sut
+            .header(http::header::ALLOW.as_str())
+            .expect("Header ALLOW not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(allow, "GET, POST");
+    }
+
+    #[test]
+    fn bad_request_should_set_status_code_to_400() {
+        let sut = bad_request().unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::BAD_REQUEST.as_u16());
+    }
+
+    #[test]
+    fn not_acceptable_should_set_status_code_to_406() {
+        let sut = not_acceptable().unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::NOT_ACCEPTABLE.as_u16());
+    }
+
+    #[test]
+    fn unsupported_media_type_should_set_status_code_to_415() {
+        let sut = unsupported_media_type().unwrap();
+        assert_eq!(
+            sut.status(),
+            &http::StatusCode::UNSUPPORTED_MEDIA_TYPE.as_u16()
+        );
+    }
+
+    #[test]
+    fn precondition_failed_should_set_status_code_to_412() {
+        let sut = precondition_failed().unwrap();
+        assert_eq!(
+            sut.status(),
+            &http::StatusCode::PRECONDITION_FAILED.as_u16()
+        );
+    }
+
+    #[test]
+    fn payload_too_large_should_set_status_code_to_413() {
+        let sut = payload_too_large().unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::PAYLOAD_TOO_LARGE.as_u16());
+    }
+
+    #[test]
+    fn reset_content_should_set_status_code_to_205() {
+        let sut = reset_content().unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::RESET_CONTENT.as_u16());
+        assert!(sut.body().is_empty());
+        assert!(sut.header(http::header::CONTENT_TYPE.as_str()).is_none());
+        assert!(sut.header(http::header::CONTENT_LENGTH.as_str()).is_none());
+    }
+
+    #[test]
+    fn bad_gateway_should_set_status_code_to_502() {
+        let sut = bad_gateway().unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::BAD_GATEWAY.as_u16());
+    }
+
+    #[test]
+    fn gateway_timeout_should_set_status_code_to_504() {
+        let sut = gateway_timeout().unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::GATEWAY_TIMEOUT.as_u16());
+    }
+
+    #[test]
+    fn not_found_should_set_status_code_to_404() {
+        let sut = not_found().unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::NOT_FOUND.as_u16());
+    }
+
+    #[test]
+    fn payment_required_should_set_status_code_to_402() {
+        let sut = payment_required().unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::PAYMENT_REQUIRED.as_u16());
+    }
+
+    #[test]
+    fn length_required_should_set_status_code_to_411() {
+        let sut = length_required().unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::LENGTH_REQUIRED.as_u16());
+    }
+
+    #[test]
+    fn redirects_should_reject_locations_containing_line_breaks() {
+        let test_data = vec!["/\r\nSet-Cookie: a=b", "/foo\nbar", "/foo\rbar"];
+        for url in test_data {
+            assert!(redirect(url, false).is_err(), "{:?}", url);
+            assert!(redirect_with_flash(url, "flash").is_err(), "{:?}", url);
+
+            let sut = ResponseBuilder::new(201).with_location(url).err();
+            assert!(matches!(sut, Some(ContribError::InvalidHeader(_))));
+        }
+    }
+
+    #[test]
+    fn named_redirects_should_set_status_code_and_location() {
+        type Redirect = fn(&str) -> Result<Response>;
+        let test_data: Vec<(Redirect, http::StatusCode)> = vec![
+            (moved_permanently, http::StatusCode::MOVED_PERMANENTLY),
+            (found, http::StatusCode::FOUND),
+            (see_other, http::StatusCode::SEE_OTHER),
+            (temporary_redirect, http::StatusCode::TEMPORARY_REDIRECT),
+            (permanent_redirect, http::StatusCode::PERMANENT_REDIRECT),
+        ];
+        for (helper, expected) in test_data {
+            let sut = helper("/target").unwrap();
+            assert_eq!(sut.status(), &expected.as_u16());
+            let location = sut
+                .header(http::header::LOCATION.as_str())
+                .expect("Header LOCATION not present")
+                .as_str()
+                .expect("Could not convert value to str");
+            assert_eq!(location, "/target");
+        }
+    }
+
+    #[test]
+    fn internal_server_error_should_set_status_code_to_500() {
+        let sut = internal_server_error().unwrap();
+        assert_eq!(
+            sut.status(),
+            &http::StatusCode::INTERNAL_SERVER_ERROR.as_u16()
+        );
+    }
+
+    #[test]
+    fn redirect_should_set_status_code_307_for_temporary() {
+        let sut = redirect("http://localhost:3000", false).unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::TEMPORARY_REDIRECT.as_u16());
+    }
+
+    #[test]
+    fn redirect_should_set_status_code_302_for_permanent() {
+        let sut = redirect("http://localhost:3000", true).unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::PERMANENT_REDIRECT.as_u16());
+    }
+
+    #[test]
+    fn redirect_should_set_location_header() {
+        let target = "http://localhost:3000";
+        let sut_permanent = redirect(target, true).unwrap();
+        let actual_permanent = sut_permanent
+            .header(http::header::LOCATION.as_str())
+            .expect("Header LOCATION not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(actual_permanent, target);
+
+        let sut_temp = redirect(target, false).unwrap();
+        let actual_temp = sut_temp
+            .header(http::header::LOCATION.as_str())
             .expect("Header LOCATION not present")
             .as_str()
             .expect("Could not convert value to str");
         assert_eq!(actual_temp, target);
     }
+
+    #[test]
+    #[cfg(feature = "anyhow")]
+    fn anyhow_helpers_should_wrap_contrib_errors() {
+        let sut = redirect("/foo\nbar", false).expect_err("redirect should fail");
+        assert!(matches!(
+            sut.downcast_ref::<ContribError>(),
+            Some(ContribError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn redirect_with_flash_should_set_status_location_and_cookie() {
+        let target = "/orders";
+        let sut = redirect_with_flash(target, "Order created; thanks!").unwrap();
+
+        assert_eq!(sut.status(), &http::StatusCode::SEE_OTHER.as_u16());
+        let location = sut
+            .header(http::header::LOCATION.as_str())
+            .expect("Header LOCATION not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(location, target);
+        let cookie = sut
+            .header(http::header::SET_COOKIE.as_str())
+            .expect("Header SET_COOKIE not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(
+            cookie,
+            "flash=Order%20created%3B%20thanks!; Max-Age=60; Path=/; HttpOnly; SameSite=Lax"
+        );
+    }
+
+    #[test]
+    fn redirect_clearing_cookie_should_set_status_location_and_removal_cookie() {
+        let sut = redirect_clearing_cookie("/login", "session").unwrap();
+
+        assert_eq!(sut.status(), &http::StatusCode::SEE_OTHER.as_u16());
+        let location = sut
+            .header(http::header::LOCATION.as_str())
+            .expect("Header LOCATION not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(location, "/login");
+        let cookie = sut
+            .header(http::header::SET_COOKIE.as_str())
+            .expect("Header SET_COOKIE not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(
+            cookie,
+            "session=; Max-Age=0; Path=/; HttpOnly; SameSite=Lax"
+        );
+    }
+
+    #[test]
+    fn csv_should_escape_fields_and_set_headers() {
+        let rows = vec![
+            vec!["name".to_string(), "quote".to_string()],
+            vec!["Doe, John".to_string(), "He said \"hi\"".to_string()],
+            vec!["plain".to_string(), "multi\nline".to_string()],
+        ];
+        let sut = csv(rows, Some("export.csv")).unwrap();
+
+        assert_eq!(sut.status(), &http::StatusCode::OK.as_u16());
+        assert_eq!(
+            String::from_utf8_lossy(sut.body()),
+            "name,quote\r\n\"Doe, John\",\"He said \"\"hi\"\"\"\r\nplain,\"multi\nline\""
+        );
+        let content_type = sut
+            .header(http::header::CONTENT_TYPE.as_str())
+            .expect("Header CONTENT_TYPE not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(content_type, "text/csv; charset=utf-8");
+        let disposition = sut
+            .header(http::header::CONTENT_DISPOSITION.as_str())
+            .expect("Header CONTENT_DISPOSITION not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(disposition, "attachment; filename=\"export.csv\"");
+    }
+
+    #[test]
+    fn sse_should_set_headers_and_serialize_events() {
+        let sut = sse(vec![
+            SseEvent::new("one").with_event("status"),
+            SseEvent::new("two"),
+        ])
+        .unwrap();
+
+        assert_eq!(sut.status(), &http::StatusCode::OK.as_u16());
+        assert_eq!(
+            String::from_utf8_lossy(sut.body()),
+            "event: status\ndata: one\n\ndata: two\n\n"
+        );
+        let content_type = sut
+            .header(http::header::CONTENT_TYPE.as_str())
+            .expect("Header CONTENT_TYPE not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(content_type, "text/event-stream; charset=utf-8");
+        let cache_control = sut
+            .header(http::header::CACHE_CONTROL.as_str())
+            .expect("Header CACHE_CONTROL not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(cache_control, "no-cache");
+    }
+
+    #[test]
+    fn csv_without_filename_should_not_set_content_disposition() {
+        let sut = csv(vec![vec!["a".to_string()]], None).unwrap();
+        assert!(sut
+            .header(http::header::CONTENT_DISPOSITION.as_str())
+            .is_none());
+    }
+
+    #[test]
+    fn contrib_response_should_compose_into_handlers() {
+        fn handler(_req: Request, _params: Params) -> ContribResponse {
+            not_found().into()
+        }
+        fn failing_handler(_req: Request, _params: Params) -> ContribResponse {
+            ContribResponse::from(Err(ContribError::PayloadTooLarge(1024)))
+        }
+        let mut router = Router::default();
+        router.get("/", handler);
+        router.get("/fail", failing_handler);
+
+        let sut = router.handle(Request::get("http://foo.bar/").build());
+        assert_eq!(sut.status(), &http::StatusCode::NOT_FOUND.as_u16());
+
+        let sut = router.handle(Request::get("http://foo.bar/fail").build());
+        assert_eq!(sut.status(), &http::StatusCode::PAYLOAD_TOO_LARGE.as_u16());
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn contrib_response_should_turn_anyhow_errors_into_internal_server_error() {
+        fn failing_handler(_req: Request, _params: Params) -> ContribResponse {
+            ContribResponse::from(Err(anyhow::anyhow!("boom")))
+        }
+        let mut router = Router::default();
+        router.get("/fail", failing_handler);
+
+        let sut = router.handle(Request::get("http://foo.bar/fail").build());
+        assert_eq!(
+            sut.status(),
+            &http::StatusCode::INTERNAL_SERVER_ERROR.as_u16()
+        );
+    }
+
+    #[test]
+    fn status_with_body_should_set_status_body_and_content_type() {
+        let sut = status_with_body(
+            http::StatusCode::IM_A_TEAPOT,
+            "short and stout",
+            Some("text/plain"),
+        )
+        .unwrap();
+
+        assert_eq!(sut.status(), &http::StatusCode::IM_A_TEAPOT.as_u16());
+        assert_eq!(sut.body(), b"short and stout");
+        let content_type = sut
+            .header(http::header::CONTENT_TYPE.as_str())
+            .expect("Header CONTENT_TYPE not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(content_type, "text/plain; charset=utf-8");
+
+        let sut = status_with_body(http::StatusCode::CONFLICT, vec![1, 2, 3], None).unwrap();
+        assert_eq!(sut.body(), &[1, 2, 3]);
+        assert!(sut.header(http::header::CONTENT_TYPE.as_str()).is_none());
+    }
+
+    #[test]
+    fn with_default_charset_should_only_extend_text_based_content_types() {
+        let test_data = vec![
+            ("text/plain", "text/plain; charset=utf-8"),
+            ("text/html;", "text/html; charset=utf-8"),
+            (
+                "text/plain; charset=iso-8859-1",
+                "text/plain; charset=iso-8859-1",
+            ),
+            (
+                "application/atom+xml",
+                "application/atom+xml; charset=utf-8",
+            ),
+            ("application/octet-stream", "application/octet-stream"),
+            ("image/png", "image/png"),
+            ("application/json", "application/json"),
+            ("invalid", "invalid"),
+        ];
+        for (content_type, expected) in test_data {
+            assert_eq!(with_default_charset(content_type), expected);
+        }
+    }
+
+    #[test]
+    fn ok_should_set_matching_content_length() {
+        let test_data = vec!["Hello, Spin", ""];
+        for body in test_data {
+            let sut = ok(body, Some("text/plain")).unwrap();
+
+            assert_eq!(sut.status(), &http::StatusCode::OK.as_u16());
+            let content_length = sut
+                .header(http::header::CONTENT_LENGTH.as_str())
+                .expect("Header CONTENT_LENGTH not present")
+                .as_str()
+                .expect("Could not convert value to str");
+            assert_eq!(content_length, body.len().to_string());
+        }
+    }
+
+    #[test]
+    fn status_with_body_should_not_set_content_length_for_bodyless_status_codes() {
+        let sut = status_with_body(http::StatusCode::NO_CONTENT, "", None).unwrap();
+        assert!(sut.header(http::header::CONTENT_LENGTH.as_str()).is_none());
+    }
+
+    #[test]
+    fn xml_should_set_status_and_content_type() {
+        let sut = xml(http::StatusCode::CREATED, "<id>1</id>").unwrap();
+
+        assert_eq!(sut.status(), &http::StatusCode::CREATED.as_u16());
+        assert_eq!(sut.body(), b"<id>1</id>");
+        let content_type = sut
+            .header(http::header::CONTENT_TYPE.as_str())
+            .expect("Header CONTENT_TYPE not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(content_type, "application/xml; charset=utf-8");
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_should_set_content_type_and_nosniff_by_default() {
+        let sut = json(http::StatusCode::OK, &vec!["a", "b"]).unwrap();
+
+        assert_eq!(sut.body(), br#"["a","b"]"#);
+        assert_eq!(
+            sut.header(http::header::CONTENT_TYPE.as_str())
+                .expect("Header CONTENT_TYPE not present")
+                .as_str()
+                .expect("Could not convert value to str"),
+            "application/json"
+        );
+        assert_eq!(
+            sut.header("X-Content-Type-Options")
+                .expect("Header X-Content-Type-Options not present")
+                .as_str()
+                .expect("Could not convert value to str"),
+            "nosniff"
+        );
+
+        let sut = json_with_nosniff(http::StatusCode::OK, &1, false).unwrap();
+        assert!(sut.header("X-Content-Type-Options").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn unprocessable_entity_json_should_return_errors_per_field() {
+        let mut errors = std::collections::HashMap::new();
+        errors.insert("email".to_string(), vec!["is invalid".to_string()]);
+        errors.insert(
+            "age".to_string(),
+            vec!["is required".to_string(), "must be a number".to_string()],
+        );
+        let sut = unprocessable_entity_json(&errors).unwrap();
+
+        assert_eq!(
+            sut.status(),
+            &http::StatusCode::UNPROCESSABLE_ENTITY.as_u16()
+        );
+        assert_eq!(
+            sut.body(),
+            br#"{"errors":{"age":["is required","must be a number"],"email":["is invalid"]}}"#
+        );
+
+        let sut = unprocessable_entity_json(&std::collections::HashMap::new()).unwrap();
+        assert_eq!(sut.body(), br#"{"errors":{}}"#);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn maybe_compress_should_compress_and_vary_on_accept_encoding() {
+        use std::io::Read;
+
+        let req = Request::builder()
+            .method(spin_sdk::http::Method::Get)
+            .uri("http://foo.bar")
+            .header(http::header::ACCEPT_ENCODING.as_str(), "br, gzip;q=0.8")
+            .build();
+        let body = "Hello, Spin! ".repeat(20);
+        let sut = maybe_compress(&req, ok(body.clone(), Some("text/plain")).unwrap());
+
+        let content_encoding = sut
+            .header(http::header::CONTENT_ENCODING.as_str())
+            .expect("Header CONTENT_ENCODING not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(content_encoding, "gzip");
+        let vary = sut
+            .header(http::header::VARY.as_str())
+            .expect("Header VARY not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(vary, "Accept-Encoding");
+
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(sut.body())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, body);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn maybe_compress_should_pass_through_but_vary_if_gzip_is_not_accepted() {
+        let test_data = vec!["", "br", "gzip;q=0"];
+        for accept_encoding in test_data {
+            let req = Request::builder()
+                .method(spin_sdk::http::Method::Get)
+                .uri("http://foo.bar")
+                .header(http::header::ACCEPT_ENCODING.as_str(), accept_encoding)
+                .build();
+            let response = ResponseBuilder::new(200)
+                .header(http::header::VARY.as_str(), "Origin")
+                .body("Hello, Spin")
+                .build();
+            let sut = maybe_compress(&req, response);
+
+            assert!(sut
+                .header(http::header::CONTENT_ENCODING.as_str())
+                .is_none());
+            assert_eq!(sut.body(), b"Hello, Spin");
+            let vary = sut
+                .header(http::header::VARY.as_str())
+                .expect("Header VARY not present")
+                .as_str()
+                .expect("Could not convert value to str");
+            assert_eq!(vary, "Origin, Accept-Encoding");
+        }
+    }
+
+    #[test]
+    fn no_cache_response_should_set_cache_control_and_pragma() {
+        let response = ResponseBuilder::new(200)
+            .header(http::header::CACHE_CONTROL.as_str(), "max-age=3600")
+            .body("token")
+            .build();
+        let sut = no_cache_response(response);
+
+        let test_data = vec![
+            (http::header::CACHE_CONTROL.as_str(), "no-store"),
+            (http::header::PRAGMA.as_str(), "no-cache"),
+        ];
+        for (header, expected) in test_data {
+            let actual = sut
+                .header(header)
+                .expect("Header not present")
+                .as_str()
+                .expect("Could not convert value to str");
+            assert_eq!(actual, expected);
+        }
+        assert_eq!(sut.body(), b"token");
+    }
+
+    #[test]
+    fn enforce_https_should_redirect_http_requests() {
+        let test_data = vec![
+            ("example.com", "https://example.com/users?page=2"),
+            ("example.com:8080", "https://example.com/users?page=2"),
+            ("[::1]:8080", "https://[::1]/users?page=2"),
+        ];
+        for (host, expected) in test_data {
+            let req = Request::builder()
+                .method(spin_sdk::http::Method::Get)
+                .uri("http://127.0.0.1:3000/users?page=2")
+                .header(http::header::HOST.as_str(), host)
+                .header("x-forwarded-proto", "http")
+                .build();
+
+            let sut = enforce_https(&req).expect("Redirect expected").unwrap();
+            assert_eq!(sut.status(), &http::StatusCode::MOVED_PERMANENTLY.as_u16());
+            let location = sut
+                .header(http::header::LOCATION.as_str())
+                .expect("Header LOCATION not present")
+                .as_str()
+                .expect("Could not convert value to str");
+            assert_eq!(location, expected);
+        }
+    }
+
+    #[test]
+    fn enforce_https_should_pass_through_https_requests() {
+        let req = Request::builder()
+            .method(spin_sdk::http::Method::Get)
+            .uri("http://127.0.0.1:3000/users")
+            .header(http::header::HOST.as_str(), "example.com")
+            .header("x-forwarded-proto", "https")
+            .build();
+
+        assert!(enforce_https(&req).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn not_found_json_should_set_status_content_type_and_message() {
+        let test_data = vec![
+            (None, r#"{"error":"not found"}"#),
+            (Some("user not found"), r#"{"error":"user not found"}"#),
+        ];
+        for (message, expected) in test_data {
+            let sut = not_found_json(message).unwrap();
+
+            assert_eq!(sut.status(), &http::StatusCode::NOT_FOUND.as_u16());
+            assert_eq!(sut.body(), expected.as_bytes());
+            let content_type = sut
+                .header(http::header::CONTENT_TYPE.as_str())
+                .expect("Header CONTENT_TYPE not present")
+                .as_str()
+                .expect("Could not convert value to str");
+            assert_eq!(content_type, "application/json");
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "json", feature = "anyhow"))]
+    fn internal_server_error_checked_should_validate_json_bodies() {
+        let sut =
+            internal_server_error_checked(r#"{"error":"boom"}"#, Some("application/json")).unwrap();
+        assert_eq!(
+            sut.status(),
+            &http::StatusCode::INTERNAL_SERVER_ERROR.as_u16()
+        );
+        assert_eq!(sut.body(), br#"{"error":"boom"}"#);
+
+        let sut = internal_server_error_checked("boom", Some("text/plain")).unwrap();
+        assert_eq!(sut.body(), b"boom");
+
+        let test_data = vec![
+            "application/json",
+            "application/problem+json; charset=utf-8",
+        ];
+        for content_type in test_data {
+            let sut = internal_server_error_checked("boom", Some(content_type)).unwrap_err();
+            assert!(matches!(
+                sut.downcast_ref::<ContribError>(),
+                Some(ContribError::Serialization(_))
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn or_bad_request_should_pass_through_deserialized_values() {
+        let sut: Vec<u32> = or_bad_request(serde_json::from_str("[1, 2]")).unwrap();
+        assert_eq!(sut, vec![1, 2]);
+
+        let sut: Vec<u32> = or_unprocessable_entity(serde_json::from_str("[3]")).unwrap();
+        assert_eq!(sut, vec![3]);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn or_bad_request_should_map_errors_to_responses() {
+        let sut = or_bad_request::<Vec<u32>>(serde_json::from_str("[1,")).unwrap_err();
+        assert_eq!(sut.status(), &http::StatusCode::BAD_REQUEST.as_u16());
+        assert!(!sut.body().is_empty());
+
+        let sut = or_unprocessable_entity::<Vec<u32>>(serde_json::from_str("{}")).unwrap_err();
+        assert_eq!(
+            sut.status(),
+            &http::StatusCode::UNPROCESSABLE_ENTITY.as_u16()
+        );
+    }
+
+    #[test]
+    fn dedupe_single_valued_should_keep_one_content_type() {
+        let mut response = ResponseBuilder::new(200)
+            .header(http::header::CONTENT_TYPE.as_str(), "application/json")
+            .header(http::header::SET_COOKIE.as_str(), "a=1")
+            .body("{}")
+            .build();
+        response.set_header("Content-Type", "text/html");
+        response.set_header("CONTENT-TYPE", "text/plain");
+        response.set_header("Set-Cookie", "b=2");
+
+        let sut = response.dedupe_single_valued();
+
+        let content_types = sut
+            .headers()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            .count();
+        assert_eq!(content_types, 1);
+        assert_eq!(
+            sut.header(http::header::CONTENT_TYPE.as_str())
+                .expect("Header CONTENT_TYPE not present")
+                .as_str()
+                .expect("Could not convert value to str"),
+            "application/json"
+        );
+        let cookies = sut
+            .headers()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+            .count();
+        assert_eq!(cookies, 2);
+        assert_eq!(sut.body(), b"{}");
+    }
+
+    #[test]
+    fn dedupe_single_valued_should_preserve_binary_values() {
+        let mut response = ResponseBuilder::new(200)
+            .headers(vec![
+                ("x-binary".to_string(), vec![0xff, 0xfe]),
+                ("content-type".to_string(), b"text/plain".to_vec()),
+            ])
+            .build();
+        response.set_header("X-Trace", "abc");
+        response.set_header("Content-Type", "text/html");
+
+        let sut = response.dedupe_single_valued();
+
+        assert_eq!(
+            sut.header("x-binary")
+                .expect("Header x-binary not present")
+                .as_bytes(),
+            &[0xff, 0xfe]
+        );
+        assert!(sut
+            .headers()
+            .any(|(name, value)| name == "X-Trace" && value.as_str() == Some("abc")));
+        assert_eq!(
+            sut.header(http::header::CONTENT_TYPE.as_str())
+                .expect("Header CONTENT_TYPE not present")
+                .as_str()
+                .expect("Could not convert value to str"),
+            "text/plain"
+        );
+    }
+
+    #[test]
+    fn dedupe_single_valued_should_keep_lexicographically_last_name_without_lowercase_variant() {
+        let mut response = ResponseBuilder::new(302).build();
+        response.set_header("CONTENT-TYPE", "text/plain");
+        response.set_header("Content-Type", "text/html");
+        response.set_header("Location", "/a");
+        response.set_header("LOCATION", "/b");
+        response.set_header("X-Trace", "abc");
+
+        let sut = response.dedupe_single_valued();
+
+        let mut headers: Vec<(String, String)> = sut
+            .headers()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    value
+                        .as_str()
+                        .expect("Could not convert value to str")
+                        .to_string(),
+                )
+            })
+            .collect();
+        headers.sort();
+        assert_eq!(
+            headers,
+            vec![
+                ("Content-Type".to_string(), "text/html".to_string()),
+                ("Location".to_string(), "/a".to_string()),
+                ("X-Trace".to_string(), "abc".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn dedupe_single_valued_should_leave_response_untouched_without_duplicates() {
+        let mut response = ResponseBuilder::new(200)
+            .headers(vec![("x-binary".to_string(), vec![0xff, 0xfe])])
+            .build();
+        response.set_header("Content-Type", "text/html");
+        response.set_header("X-Trace", "abc");
+
+        let sut = response.dedupe_single_valued();
+
+        assert_eq!(sut.headers().count(), 3);
+        assert!(sut
+            .headers()
+            .any(|(name, value)| name == "Content-Type" && value.as_str() == Some("text/html")));
+        assert_eq!(
+            sut.header("x-binary")
+                .expect("Header x-binary not present")
+                .as_bytes(),
+            &[0xff, 0xfe]
+        );
+    }
+
+    #[test]
+    fn into_parts_and_into_response_should_preserve_response() {
+        let response = ResponseBuilder::new(http::StatusCode::ACCEPTED)
+            .header(http::header::CONTENT_TYPE.as_str(), "text/plain")
+            .header(http::header::SET_COOKIE.as_str(), "a=b")
+            .body("Hello, Spin")
+            .build();
+
+        let (parts, body) = response.into_parts();
+        assert_eq!(parts.status, http::StatusCode::ACCEPTED.as_u16());
+        assert_eq!(parts.headers.len(), 2);
+        assert_eq!(
+            parts
+                .header("Content-Type")
+                .expect("Header CONTENT_TYPE not present")
+                .as_str(),
+            Some("text/plain")
+        );
+        assert_eq!(body, b"Hello, Spin");
+
+        let sut = parts.into_response(body);
+        assert_eq!(sut.status(), &http::StatusCode::ACCEPTED.as_u16());
+        assert_eq!(sut.headers().count(), 2);
+        let test_data = vec![
+            (http::header::CONTENT_TYPE.as_str(), "text/plain"),
+            (http::header::SET_COOKIE.as_str(), "a=b"),
+        ];
+        for (header, expected) in test_data {
+            let actual = sut
+                .header(header)
+                .expect("Header not present")
+                .as_str()
+                .expect("Could not convert value to str");
+            assert_eq!(actual, expected);
+        }
+        assert_eq!(sut.body(), b"Hello, Spin");
+    }
+
+    #[test]
+    fn response_parts_set_header_should_replace_existing_values() {
+        let (mut sut, _) = ResponseBuilder::new(200)
+            .header(http::header::CONTENT_TYPE.as_str(), "text/plain")
+            .build()
+            .into_parts();
+        sut.set_header("Content-Type", "application/json");
+
+        assert_eq!(sut.headers.len(), 1);
+        assert_eq!(
+            sut.header("content-type")
+                .expect("Header CONTENT_TYPE not present")
+                .as_str(),
+            Some("application/json")
+        );
+    }
+
+    #[test]
+    fn im_a_teapot_should_set_status_code_to_418() {
+        let sut = im_a_teapot().unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::IM_A_TEAPOT.as_u16());
+        assert!(!sut.body().is_empty());
+    }
+
+    #[test]
+    fn with_vary_should_merge_into_existing_vary_header() {
+        let sut = ResponseBuilder::new(200)
+            .header(http::header::VARY.as_str(), "Accept-Encoding")
+            .with_vary(&["Accept"])
+            .build();
+
+        let actual = sut
+            .header(http::header::VARY.as_str())
+            .expect("Header VARY not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(actual, "Accept-Encoding, Accept");
+    }
+
+    #[test]
+    fn with_request_id_should_set_header() {
+        let sut = ResponseBuilder::new(200).with_request_id("abc-123").build();
+
+        let actual = sut
+            .header(HEADER_X_REQUEST_ID)
+            .expect("Header X-Request-Id not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(actual, "abc-123");
+    }
+
+    #[test]
+    fn with_server_timing_should_serialize_metrics() {
+        let mut timing = ServerTiming::new();
+        timing.add("db", Some(53.0), Some("Query \"users\"")).add(
+            "cache",
+            None,
+            Some("Cache Read"),
+        );
+        let sut = ResponseBuilder::new(200)
+            .with_server_timing(&timing)
+            .build();
+
+        let actual = sut
+            .header(HEADER_SERVER_TIMING)
+            .expect("Header Server-Timing not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(
+            actual,
+            "db;dur=53;desc=\"Query \\\"users\\\"\", cache;desc=\"Cache Read\""
+        );
+    }
+
+    #[test]
+    fn with_server_timing_should_skip_empty_metrics() {
+        let sut = ResponseBuilder::new(200)
+            .with_server_timing(&ServerTiming::new())
+            .build();
+
+        assert!(sut.header(HEADER_SERVER_TIMING).is_none());
+    }
+
+    #[test]
+    fn with_expires_and_age_should_set_caching_headers() {
+        let expires = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_717_459_200 + 3_723);
+        let sut = ResponseBuilder::new(200)
+            .with_expires(expires)
+            .with_age(120)
+            .build();
+
+        let actual_expires = sut
+            .header(http::header::EXPIRES.as_str())
+            .expect("Header EXPIRES not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(actual_expires, "Tue, 04 Jun 2024 01:02:03 GMT");
+        let actual_age = sut
+            .header(http::header::AGE.as_str())
+            .expect("Header AGE not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(actual_age, "120");
+    }
+
+    #[test]
+    #[cfg(feature = "etag")]
+    fn etag_for_should_be_stable_for_identical_bodies() {
+        assert_eq!(etag_for(b"Hello, Spin"), etag_for(b"Hello, Spin"));
+        assert_ne!(etag_for(b"Hello, Spin"), etag_for(b"Hello, Fermyon"));
+        assert_eq!(etag_for(b"").len(), ETAG_HEX_LENGTH + 2);
+    }
+
+    #[test]
+    #[cfg(feature = "etag")]
+    fn with_etag_from_body_should_set_etag_header() {
+        let sut = ResponseBuilder::new(200)
+            .body("Hello, Spin")
+            .with_etag_from_body()
+            .build();
+
+        let actual = sut
+            .header(http::header::ETAG.as_str())
+            .expect("Header ETAG not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(actual, etag_for(b"Hello, Spin"));
+        assert_eq!(sut.body(), b"Hello, Spin");
+    }
+
+    #[test]
+    fn status_class_predicates_should_match_status_codes() {
+        let test_data = vec![
+            (200, [true, false, false, false]),
+            (204, [true, false, false, false]),
+            (302, [false, true, false, false]),
+            (404, [false, false, true, false]),
+            (500, [false, false, false, true]),
+            (101, [false, false, false, false]),
+        ];
+        for (status, expected) in test_data {
+            let sut = Response::new(status, ());
+            let actual = [
+                sut.is_success(),
+                sut.is_redirect(),
+                sut.is_client_error(),
+                sut.is_server_error(),
+            ];
+            assert_eq!(actual, expected, "Status: {}", status);
+        }
+    }
+
+    #[test]
+    fn merge_vary_should_deduplicate_case_insensitively() {
+        let test_data = vec![
+            ("", vec!["Accept"], "Accept"),
+            ("Accept", vec!["accept"], "Accept"),
+            (
+                "Accept, Origin",
+                vec!["origin", "Accept-Encoding"],
+                "Accept, Origin, Accept-Encoding",
+            ),
+            ("Origin", vec!["*"], "*"),
+            ("", vec![], ""),
+        ];
+        for (existing, fields, expected) in test_data {
+            assert_eq!(merge_vary(existing, &fields), expected);
+        }
+    }
 }
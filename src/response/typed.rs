@@ -0,0 +1,407 @@
+use spin_sdk::http::{Method, Request, Response};
+
+use crate::cookies::{encode_cookie_value, Cookie, CookieResponseBuilder, SameSite};
+use crate::error::ContribError;
+#[cfg(feature = "json")]
+use crate::mediatype::MediaType;
+use crate::request::Contrib;
+use crate::sse::SseEvent;
+
+#[cfg(feature = "json")]
+use super::HEADER_X_CONTENT_TYPE_OPTIONS;
+use super::{
+    allows_body, escape_csv_field, quoted_string, strip_port, with_default_charset,
+    ContribResponseBuilder, FLASH_COOKIE_MAX_AGE, FLASH_COOKIE_NAME,
+};
+
+/// Same as [`super::redirect`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn redirect(url: &str, permanent: bool) -> Result<Response, ContribError> {
+    if permanent {
+        return permanent_redirect(url);
+    }
+    temporary_redirect(url)
+}
+
+/// Same as [`super::moved_permanently`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn moved_permanently(url: &str) -> Result<Response, ContribError> {
+    redirect_with_status(url, http::StatusCode::MOVED_PERMANENTLY)
+}
+
+/// Same as [`super::found`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn found(url: &str) -> Result<Response, ContribError> {
+    redirect_with_status(url, http::StatusCode::FOUND)
+}
+
+/// Same as [`super::see_other`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn see_other(url: &str) -> Result<Response, ContribError> {
+    redirect_with_status(url, http::StatusCode::SEE_OTHER)
+}
+
+/// Same as [`super::temporary_redirect`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn temporary_redirect(url: &str) -> Result<Response, ContribError> {
+    redirect_with_status(url, http::StatusCode::TEMPORARY_REDIRECT)
+}
+
+/// Same as [`super::permanent_redirect`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn permanent_redirect(url: &str) -> Result<Response, ContribError> {
+    redirect_with_status(url, http::StatusCode::PERMANENT_REDIRECT)
+}
+
+fn redirect_with_status(url: &str, status: http::StatusCode) -> Result<Response, ContribError> {
+    Ok(Response::builder()
+        .status(status)
+        .with_location(url)?
+        .body(())
+        .build())
+}
+
+/// Same as [`super::enforce_https`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn enforce_https(req: &Request) -> Option<Result<Response, ContribError>> {
+    if req.scheme() == "https" {
+        return None;
+    }
+    let host = req.host().or_else(|| {
+        req.uri()
+            .parse::<http::Uri>()
+            .ok()
+            .and_then(|uri| uri.authority().map(|authority| authority.to_string()))
+    });
+    let Some(host) = host else {
+        return Some(bad_request());
+    };
+    let location = format!(
+        "https://{}{}",
+        strip_port(&host),
+        req.path_and_query().unwrap_or("/")
+    );
+    Some(moved_permanently(&location))
+}
+
+/// Same as [`super::redirect_with_flash`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn redirect_with_flash(url: &str, flash: &str) -> Result<Response, ContribError> {
+    let cookie = Cookie::new(
+        FLASH_COOKIE_NAME,
+        encode_cookie_value(flash),
+        false,
+        true,
+        SameSite::Lax,
+    )
+    .with_max_age(FLASH_COOKIE_MAX_AGE)
+    .with_path("/");
+    Ok(Response::builder()
+        .status(http::StatusCode::SEE_OTHER)
+        .with_location(url)?
+        .body(())
+        .build_with_cookie(cookie))
+}
+
+/// Same as [`super::redirect_clearing_cookie`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn redirect_clearing_cookie(url: &str, cookie_name: &str) -> Result<Response, ContribError> {
+    Ok(Response::builder()
+        .status(http::StatusCode::SEE_OTHER)
+        .with_location(url)?
+        .body(())
+        .build_with_cookie(Cookie::removal(cookie_name)))
+}
+
+/// Same as [`super::csv`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn csv(
+    rows: impl IntoIterator<Item = Vec<String>>,
+    filename: Option<&str>,
+) -> Result<Response, ContribError> {
+    let body = rows
+        .into_iter()
+        .map(|row| {
+            row.iter()
+                .map(|field| escape_csv_field(field))
+                .collect::<Vec<String>>()
+                .join(",")
+        })
+        .collect::<Vec<String>>()
+        .join("\r\n");
+
+    let mut response =
+        status_with_body(http::StatusCode::OK, body, Some("text/csv; charset=utf-8"))?;
+    if let Some(filename) = filename {
+        let filename = filename
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect::<String>();
+        response.set_header(
+            http::header::CONTENT_DISPOSITION.as_str(),
+            format!("attachment; filename={}", quoted_string(&filename)),
+        );
+    }
+    Ok(response)
+}
+
+/// Same as [`super::sse`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn sse(events: impl IntoIterator<Item = SseEvent>) -> Result<Response, ContribError> {
+    let body = events
+        .into_iter()
+        .map(|event| event.to_string())
+        .collect::<String>();
+    let mut response = status_with_body(http::StatusCode::OK, body, Some("text/event-stream"))?;
+    response.set_header(http::header::CACHE_CONTROL.as_str(), "no-cache");
+    Ok(response)
+}
+
+/// Same as [`super::bad_request`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn bad_request() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::BAD_REQUEST)
+}
+
+/// Same as [`super::no_content`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn no_content() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::NO_CONTENT)
+}
+
+/// Same as [`super::not_acceptable`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn not_acceptable() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::NOT_ACCEPTABLE)
+}
+
+/// Same as [`super::unsupported_media_type`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn unsupported_media_type() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::UNSUPPORTED_MEDIA_TYPE)
+}
+
+/// Same as [`super::method_not_allowed`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn method_not_allowed(allowed: &[Method]) -> Result<Response, ContribError> {
+    let allow = allowed
+        .iter()
+        .map(|method| method.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    Ok(Response::builder()
+        .status(http::StatusCode::METHOD_NOT_ALLOWED)
+        .header(http::header::ALLOW.as_str(), allow)
+        .body(())
+        .build())
+}
+
+/// Same as [`super::precondition_failed`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn precondition_failed() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::PRECONDITION_FAILED)
+}
+
+/// Same as [`super::payload_too_large`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn payload_too_large() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::PAYLOAD_TOO_LARGE)
+}
+
+/// Same as [`super::reset_content`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn reset_content() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::RESET_CONTENT)
+}
+
+/// Same as [`super::bad_gateway`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn bad_gateway() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::BAD_GATEWAY)
+}
+
+/// Same as [`super::gateway_timeout`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn gateway_timeout() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::GATEWAY_TIMEOUT)
+}
+
+/// Same as [`super::not_found`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn not_found() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::NOT_FOUND)
+}
+
+/// Same as [`super::payment_required`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn payment_required() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::PAYMENT_REQUIRED)
+}
+
+/// Same as [`super::length_required`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn length_required() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::LENGTH_REQUIRED)
+}
+
+/// Same as [`super::internal_server_error`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn internal_server_error() -> Result<Response, ContribError> {
+    create_response(http::StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Same as [`super::status_code`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn status_code(status_code: http::StatusCode) -> Result<Response, ContribError> {
+    create_response(status_code)
+}
+
+/// Same as [`super::status_with_body`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn status_with_body(
+    code: http::StatusCode,
+    body: impl Into<bytes::Bytes>,
+    content_type: Option<&str>,
+) -> Result<Response, ContribError> {
+    let body = body.into();
+    let mut builder = Response::builder();
+    builder.status(code);
+    if let Some(content_type) = content_type {
+        builder.header(
+            http::header::CONTENT_TYPE.as_str(),
+            with_default_charset(content_type),
+        );
+    }
+    if allows_body(code) {
+        builder.header(
+            http::header::CONTENT_LENGTH.as_str(),
+            body.len().to_string(),
+        );
+    }
+    Ok(builder.body(body).build())
+}
+
+/// Same as [`super::ok`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn ok(
+    body: impl Into<bytes::Bytes>,
+    content_type: Option<&str>,
+) -> Result<Response, ContribError> {
+    status_with_body(http::StatusCode::OK, body, content_type)
+}
+
+/// Same as [`super::xml`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn xml(
+    status: http::StatusCode,
+    body: impl Into<bytes::Bytes>,
+) -> Result<Response, ContribError> {
+    status_with_body(status, body, Some("application/xml; charset=utf-8"))
+}
+
+/// Same as [`super::json`], but returns a `ContribError` instead of an `anyhow::Error`
+#[cfg(feature = "json")]
+pub fn json<T: serde::Serialize + ?Sized>(
+    status: http::StatusCode,
+    value: &T,
+) -> Result<Response, ContribError> {
+    json_with_nosniff(status, value, true)
+}
+
+/// Same as [`super::json_with_nosniff`], but returns a `ContribError` instead of an `anyhow::Error`
+#[cfg(feature = "json")]
+pub fn json_with_nosniff<T: serde::Serialize + ?Sized>(
+    status: http::StatusCode,
+    value: &T,
+    nosniff: bool,
+) -> Result<Response, ContribError> {
+    let body = serde_json::to_vec(value).map_err(|e| ContribError::Serialization(e.to_string()))?;
+    let mut response = status_with_body(status, body, Some("application/json"))?;
+    if nosniff {
+        response.set_header(HEADER_X_CONTENT_TYPE_OPTIONS, "nosniff");
+    }
+    Ok(response)
+}
+
+/// Same as [`super::unprocessable_entity_json`], but returns a `ContribError` instead of an `anyhow::Error`
+#[cfg(feature = "json")]
+pub fn unprocessable_entity_json(
+    errors: &std::collections::HashMap<String, Vec<String>>,
+) -> Result<Response, ContribError> {
+    let errors = errors.iter().collect::<std::collections::BTreeMap<_, _>>();
+    json(
+        http::StatusCode::UNPROCESSABLE_ENTITY,
+        &serde_json::json!({ "errors": errors }),
+    )
+}
+
+/// Same as [`super::not_found_json`], but returns a `ContribError` instead of an `anyhow::Error`
+#[cfg(feature = "json")]
+pub fn not_found_json(message: Option<&str>) -> Result<Response, ContribError> {
+    json(
+        http::StatusCode::NOT_FOUND,
+        &serde_json::json!({ "error": message.unwrap_or("not found") }),
+    )
+}
+
+/// Same as [`super::internal_server_error_checked`], but returns a `ContribError` instead of an `anyhow::Error`
+#[cfg(feature = "json")]
+pub fn internal_server_error_checked(
+    body: impl Into<bytes::Bytes>,
+    content_type: Option<&str>,
+) -> Result<Response, ContribError> {
+    let body = body.into();
+    let is_json = content_type
+        .and_then(MediaType::parse)
+        .is_some_and(|media_type| media_type.is_json());
+    if is_json {
+        if let Err(e) = serde_json::from_slice::<serde::de::IgnoredAny>(&body) {
+            return Err(ContribError::Serialization(format!(
+                "body does not match content type {}: {}",
+                content_type.unwrap_or_default(),
+                e
+            )));
+        }
+    }
+    status_with_body(http::StatusCode::INTERNAL_SERVER_ERROR, body, content_type)
+}
+
+/// Same as [`super::im_a_teapot`], but returns a `ContribError` instead of an `anyhow::Error`
+pub fn im_a_teapot() -> Result<Response, ContribError> {
+    status_with_body(
+        http::StatusCode::IM_A_TEAPOT,
+        "I'm a teapot",
+        Some("text/plain; charset=utf-8"),
+    )
+}
+
+fn create_response(status_code: http::StatusCode) -> Result<Response, ContribError> {
+    Ok(Response::new(status_code, ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use spin_sdk::http::IntoResponse;
+
+    use super::*;
+
+    #[test]
+    fn typed_helpers_should_produce_the_same_responses() {
+        let sut = ok("Hello, Spin", Some("text/plain")).unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::OK.as_u16());
+        assert_eq!(sut.body(), b"Hello, Spin");
+        let actual = sut
+            .header(http::header::CONTENT_TYPE.as_str())
+            .expect("Header CONTENT_TYPE not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(actual, "text/plain; charset=utf-8");
+
+        let sut = status_with_body(http::StatusCode::CONFLICT, "exists", None).unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::CONFLICT.as_u16());
+
+        let sut = redirect("https://example.com", true).unwrap();
+        assert_eq!(sut.status(), &http::StatusCode::PERMANENT_REDIRECT.as_u16());
+        let actual = sut
+            .header(http::header::LOCATION.as_str())
+            .expect("Header LOCATION not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(actual, "https://example.com");
+    }
+
+    #[test]
+    fn typed_helpers_should_return_contrib_errors() {
+        let test_data = vec!["https://example.com/\r\nSet-Cookie: a=b", "/foo\nbar"];
+        for url in test_data {
+            let sut = redirect(url, false).expect_err("redirect should fail");
+            assert!(matches!(sut, ContribError::InvalidHeader(_)));
+            assert_eq!(
+                sut.into_response().status(),
+                &http::StatusCode::INTERNAL_SERVER_ERROR.as_u16()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn typed_json_helpers_should_return_contrib_errors() {
+        let sut = json(http::StatusCode::OK, &vec!["Hello", "Spin"]).unwrap();
+        assert_eq!(sut.body(), br#"["Hello","Spin"]"#);
+
+        let sut = internal_server_error_checked("boom", Some("application/json"))
+            .expect_err("invalid JSON should be rejected");
+        assert!(matches!(sut, ContribError::Serialization(_)));
+    }
+}
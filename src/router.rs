@@ -0,0 +1,172 @@
+use spin_sdk::http::{Method, Params, Request, Response, Router};
+
+use crate::error::ContribError;
+use crate::response::typed::{method_not_allowed, ok, status_with_body};
+
+const HEALTHY: &str = "ok";
+const UNHEALTHY: &str = "unavailable";
+const TEXT_PLAIN: &str = "text/plain; charset=utf-8";
+/// Methods answered with `405 Method Not Allowed` by `register_method_not_allowed` (unless allowed)
+const STANDARD_METHODS: [Method; 7] = [
+    Method::Get,
+    Method::Head,
+    Method::Post,
+    Method::Put,
+    Method::Patch,
+    Method::Delete,
+    Method::Trace,
+];
+
+/// Extensions for spin_sdk::http::Router
+pub trait ContribRouter {
+    /// Registers a health check (liveness) endpoint responding with `200 OK` and `ok` as `text/plain`
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the health check endpoint (e.g. `/healthz`)
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::Router;
+    /// use spin_contrib_http::router::ContribRouter;
+    ///
+    /// let mut router = Router::default();
+    /// router.register_health_check("/healthz");
+    /// ```
+    fn register_health_check(&mut self, path: &str);
+
+    /// Registers a readiness check endpoint using a custom check.
+    ///
+    /// The endpoint responds with `200 OK` if the check returns `true`, otherwise with `503 Service Unavailable`
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the readiness check endpoint (e.g. `/readyz`)
+    /// * `check` - Determines whether or not the component is ready to handle requests
+    fn register_readiness_check<F>(&mut self, path: &str, check: F)
+    where
+        F: Fn() -> bool + 'static;
+
+    /// Registers handlers responding with `405 Method Not Allowed` and an `Allow` header listing the provided
+    /// methods for all other methods sent to the path.
+    ///
+    /// Register the handlers of the allowed methods as usual. `HEAD` keeps falling back to `GET` if `GET` is allowed,
+    /// and `OPTIONS` is left alone, so CORS preflights are still handled
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the resource (e.g. `/todos`)
+    /// * `methods` - The methods supported by the resource
+    ///
+    /// # Example
+    /// ```rust
+    /// use spin_sdk::http::{Method, Params, Request, Response, Router};
+    /// use spin_contrib_http::router::ContribRouter;
+    ///
+    /// let mut router = Router::default();
+    /// router.get("/todos", |_: Request, _: Params| Response::new(200, ()));
+    /// router.post("/todos", |_: Request, _: Params| Response::new(201, ()));
+    /// router.register_method_not_allowed("/todos", &[Method::Get, Method::Post]);
+    /// ```
+    fn register_method_not_allowed(&mut self, path: &str, methods: &[Method]);
+}
+
+impl ContribRouter for Router {
+    fn register_health_check(&mut self, path: &str) {
+        self.register_readiness_check(path, || true)
+    }
+
+    fn register_readiness_check<F>(&mut self, path: &str, check: F)
+    where
+        F: Fn() -> bool + 'static,
+    {
+        self.get(
+            path,
+            move |_: Request, _: Params| -> Result<Response, ContribError> {
+                if check() {
+                    return ok(HEALTHY, Some(TEXT_PLAIN));
+                }
+                status_with_body(
+                    http::StatusCode::SERVICE_UNAVAILABLE,
+                    UNHEALTHY,
+                    Some(TEXT_PLAIN),
+                )
+            },
+        )
+    }
+
+    fn register_method_not_allowed(&mut self, path: &str, methods: &[Method]) {
+        let get_allowed = methods.contains(&Method::Get);
+        for method in STANDARD_METHODS {
+            if methods.contains(&method) || (method == Method::Head && get_allowed) {
+                continue;
+            }
+            let allowed = methods.to_vec();
+            self.add(
+                path,
+                method,
+                move |_: Request, _: Params| -> Result<Response, ContribError> {
+                    method_not_allowed(&allowed)
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spin_sdk::http::{Request, Router};
+
+    use super::*;
+
+    #[test]
+    fn registered_health_check_should_return_ok() {
+        let mut router = Router::default();
+        router.register_health_check("/healthz");
+
+        let sut = router.handle(Request::get("http://foo.bar/healthz").build());
+        assert_eq!(sut.status(), &http::StatusCode::OK.as_u16());
+        assert_eq!(sut.body(), HEALTHY.as_bytes());
+        assert_eq!(
+            sut.header(http::header::CONTENT_TYPE.as_str())
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            TEXT_PLAIN
+        );
+    }
+
+    #[test]
+    fn registered_readiness_check_should_return_service_unavailable_if_not_ready() {
+        let mut router = Router::default();
+        router.register_readiness_check("/readyz", || false);
+
+        let sut = router.handle(Request::get("http://foo.bar/readyz").build());
+        assert_eq!(
+            sut.status(),
+            &http::StatusCode::SERVICE_UNAVAILABLE.as_u16()
+        );
+    }
+
+    #[test]
+    fn registered_method_not_allowed_should_list_allowed_methods() {
+        let mut router = Router::default();
+        router.get("/todos", |_: Request, _: Params| Response::new(200, ()));
+        router.post("/todos", |_: Request, _: Params| Response::new(201, ()));
+        router.register_method_not_allowed("/todos", &[Method::Get, Method::Post]);
+
+        let sut = router.handle(Request::delete("http://foo.bar/todos").build());
+        assert_eq!(sut.status(), &http::StatusCode::METHOD_NOT_ALLOWED.as_u16());
+        let allow = sut
+            .header(http::header::ALLOW.as_str())
+            .expect("Header ALLOW not present")
+            .as_str()
+            .expect("Could not convert value to str");
+        assert_eq!(allow, "GET, POST");
+
+        let sut = router.handle(Request::post("http://foo.bar/todos", ()).build());
+        assert_eq!(sut.status(), &http::StatusCode::CREATED.as_u16());
+        let sut = router.handle(Request::new(Method::Head, "http://foo.bar/todos"));
+        assert_eq!(sut.status(), &http::StatusCode::OK.as_u16());
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use futures::{SinkExt, Stream, StreamExt};
+use spin_sdk::http::{Fields, OutgoingResponse, ResponseOutparam};
+
+use crate::error::ContribError;
+
+/// Representation of a single Server-Sent Event
+///
+/// The `Display` implementation produces the wire format of the event (terminated by an empty line),
+/// so events can either be sent at once (see `response::sse`) or written one by one to a streaming body (see `send_events`)
+#[derive(Debug, Clone)]
+pub struct SseEvent {
+    /// The data of the event
+    data: String,
+    /// The type of the event (`event:` field)
+    event: Option<String>,
+    /// The id of the event (`id:` field)
+    id: Option<String>,
+    /// The reconnection time (`retry:` field)
+    retry: Option<Duration>,
+}
+
+impl SseEvent {
+    /// Creates a new event carrying the provided data.
+    ///
+    /// Multi-line data is sent as multiple `data:` fields, which clients join using `\n`
+    pub fn new(data: impl Into<String>) -> Self {
+        SseEvent {
+            data: data.into(),
+            event: None,
+            id: None,
+            retry: None,
+        }
+    }
+
+    /// Sets the type of the event. Clients dispatch the event to listeners registered for this type
+    pub fn with_event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Sets the id of the event. Clients send the last id as `Last-Event-ID` when reconnecting
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets the time clients should wait before reconnecting (sent in milliseconds)
+    pub fn with_retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+}
+
+/// Removes line breaks (and NUL for ids), which would otherwise terminate the field and allow injecting fields
+fn single_line(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '\r' | '\n' | '\0'))
+        .collect()
+}
+
+/// Serializes the event in the `text/event-stream` wire format
+///
+/// Fields are emitted in the order `event`, `id`, `retry`, `data`, followed by an empty line
+impl Display for SseEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {}", single_line(event))?;
+        }
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {}", single_line(id))?;
+        }
+        if let Some(retry) = self.retry {
+            writeln!(f, "retry: {}", retry.as_millis())?;
+        }
+        let data = self.data.replace("\r\n", "\n").replace('\r', "\n");
+        for line in data.split('\n') {
+            writeln!(f, "data: {}", line)?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Encodes the provided events in the `text/event-stream` wire format, yielding one chunk per event
+pub fn encode_events<S>(events: S) -> impl Stream<Item = Vec<u8>>
+where
+    S: Stream<Item = SseEvent>,
+{
+    events.map(|event| event.to_string().into_bytes())
+}
+
+/// Sends the provided events to the client as a streaming response, writing each event as soon as it is produced
+///
+/// The response is sent using `response_out` with status `200 OK` and the headers used by `response::sse`
+/// (`Content-Type: text/event-stream; charset=utf-8` and `Cache-Control: no-cache`).
+/// Returns once the stream of events ends, or `ContribError::Stream` if an event can't be written
+/// (e.g. because the client disconnected)
+///
+/// # Example
+/// ```no_run
+/// use futures::StreamExt;
+/// use spin_sdk::http::{IncomingRequest, ResponseOutparam};
+/// use spin_contrib_http::sse::{send_events, SseEvent};
+///
+/// async fn handle(_req: IncomingRequest, response_out: ResponseOutparam) {
+///   let events = futures::stream::iter(1..=3).map(|i| SseEvent::new(i.to_string()).with_id(i.to_string()));
+///   if let Err(e) = send_events(events, response_out).await {
+///     eprintln!("{}", e);
+///   }
+/// }
+/// ```
+pub async fn send_events<S>(events: S, response_out: ResponseOutparam) -> Result<(), ContribError>
+where
+    S: Stream<Item = SseEvent>,
+{
+    let headers = Fields::from_list(&[
+        (
+            http::header::CONTENT_TYPE.to_string(),
+            b"text/event-stream; charset=utf-8".to_vec(),
+        ),
+        (
+            http::header::CACHE_CONTROL.to_string(),
+            b"no-cache".to_vec(),
+        ),
+    ])
+    .map_err(|e| ContribError::InvalidHeader(format!("{:?}", e)))?;
+    let response = OutgoingResponse::new(headers);
+    let mut body = response.take_body();
+    response_out.set(response);
+
+    let mut chunks = std::pin::pin!(encode_events(events));
+    while let Some(chunk) = chunks.next().await {
+        body.send(chunk).await.map_err(|e| {
+            ContribError::Stream(format!("writing the response body failed: {:?}", e))
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_event_should_serialize_to_wire_format() {
+        let sut = SseEvent::new("hello")
+            .with_event("greeting")
+            .with_id("42")
+            .with_retry(Duration::from_secs(3));
+
+        assert_eq!(
+            sut.to_string(),
+            "event: greeting\nid: 42\nretry: 3000\ndata: hello\n\n"
+        );
+    }
+
+    #[test]
+    fn sse_event_should_prefix_each_line_of_data() {
+        let test_data = vec![
+            ("a\nb", "data: a\ndata: b\n\n"),
+            ("a\r\nb\rc", "data: a\ndata: b\ndata: c\n\n"),
+            ("", "data: \n\n"),
+        ];
+        for (data, expected) in test_data {
+            assert_eq!(SseEvent::new(data).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn sse_event_should_strip_line_breaks_from_event_and_id() {
+        let sut = SseEvent::new("x")
+            .with_event("a\ndata: injected")
+            .with_id("1\r\n2");
+        assert_eq!(
+            sut.to_string(),
+            "event: adata: injected\nid: 12\ndata: x\n\n"
+        );
+    }
+
+    #[test]
+    fn encode_events_should_yield_one_chunk_per_event() {
+        let events = futures::stream::iter(vec![
+            SseEvent::new("one").with_event("status"),
+            SseEvent::new("two").with_id("2"),
+        ]);
+
+        let sut = futures::executor::block_on(encode_events(events).collect::<Vec<Vec<u8>>>());
+
+        assert_eq!(
+            sut,
+            vec![
+                b"event: status\ndata: one\n\n".to_vec(),
+                b"id: 2\ndata: two\n\n".to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn sse_event_should_be_cloneable() {
+        let event = SseEvent::new("hello").with_event("greeting");
+        let sut = event.clone();
+        assert_eq!(sut.to_string(), event.to_string());
+        assert!(format!("{:?}", sut).contains("greeting"));
+    }
+}